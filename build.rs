@@ -1,6 +1,39 @@
-use std::{fs, process::Command};
+use std::{env, fs, process::Command};
 
-fn compile_po_files(languages: &str) {
+const POT_FILE: &str = "po/vaktisalah-gtk-rs.pot";
+
+/// The `"POT-Creation-Date: ..."` header changes on every run of xgettext.
+fn without_creation_date(contents: &str) -> String {
+    contents
+        .lines()
+        .filter(|line| !line.starts_with("\"POT-Creation-Date:"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Copies a file generated in `OUT_DIR` into the sources only when it changed, so an
+/// up-to-date checkout isn't written (e.g. read-only sources) and this script isn't
+/// rerun for its own output.
+fn update_if_changed(generated: &str, path: &str) {
+    let Ok(new) = fs::read(generated) else {
+        return;
+    };
+    let old = fs::read(path).unwrap_or_default();
+
+    let changed = match (
+        String::from_utf8(new.clone()),
+        String::from_utf8(old.clone()),
+    ) {
+        (Ok(new), Ok(old)) => without_creation_date(&new) != without_creation_date(&old),
+        _ => new != old,
+    };
+
+    if changed {
+        fs::write(path, new).unwrap();
+    }
+}
+
+fn compile_po_files(languages: &str, out_dir: &str) {
     // Create compiled translations dir:
 
     for i in languages.split_whitespace() {
@@ -10,64 +43,115 @@ fn compile_po_files(languages: &str) {
         let lang_file = format!("po/{}.po", i);
         if fs::exists(&lang_file).unwrap() {
             // File exists, update contents:
+            let generated = format!("{out_dir}/{i}.mo");
             Command::new("msgfmt")
-                .args([
-                    &lang_file,
-                    "-o",
-                    &format!("{}/vaktisalah-gtk-rs.mo", &output_path),
-                ])
+                .args([&lang_file, "-o", &generated])
                 .output()
                 .unwrap();
+
+            update_if_changed(
+                &generated,
+                &format!("{}/vaktisalah-gtk-rs.mo", &output_path),
+            );
         }
     }
 }
 
-fn update_po_files(languages: &str) {
+fn update_po_files(languages: &str, out_dir: &str) {
     for i in languages.split_whitespace() {
         let lang_file = format!("po/{}.po", i);
         if fs::exists(&lang_file).unwrap() {
             // File exists, update contents:
+            let generated = format!("{out_dir}/{i}.po");
             Command::new("msgmerge")
-                .args(["-o", &lang_file, &lang_file, "po/vaktisalah-gtk-rs.pot"])
+                // Guesses from similar messages are mostly wrong for short UI strings
+                .args([
+                    "--no-fuzzy-matching",
+                    "-o",
+                    &generated,
+                    &lang_file,
+                    POT_FILE,
+                ])
                 .output()
                 .unwrap();
+
+            update_if_changed(&generated, &lang_file);
         } else {
             // Create new translation file
             Command::new("cp")
-                .args(["po/vaktisalah-gtk-rs.pot", &lang_file])
+                .args([POT_FILE, &lang_file])
                 .output()
                 .unwrap();
         }
     }
 }
 
-fn create_pot_file() {
+fn create_pot_file(out_dir: &str) {
     // Generate translations (using "Python" for .blp files works)
+    let generated = format!("{out_dir}/vaktisalah-gtk-rs.pot");
     Command::new("xgettext")
-        .args([
-            "-o",
-            "po/vaktisalah-gtk-rs.pot",
-            "-f",
-            "po/POTFILES",
-            "-L",
-            "Python",
-        ])
+        .args(["-o", &generated, "-f", "po/POTFILES", "-L", "Python"])
         .output()
         .unwrap();
+
+    update_if_changed(&generated, POT_FILE);
+}
+
+fn compile_schemas() {
+    // preferences.json is used instead
+    if env::var_os("CARGO_FEATURE_JSON_BACKEND").is_some() {
+        return;
+    }
+
+    // Compiled for running without installing (e.g. `cargo run`),
+    // packages install the .xml file and compile it themselves.
+    let target_dir = format!("{}/schemas", env::var("OUT_DIR").unwrap());
+    fs::create_dir_all(&target_dir).unwrap();
+
+    let output = Command::new("glib-compile-schemas")
+        .args(["--strict", "--targetdir", &target_dir, "data/"])
+        .output()
+        .unwrap_or_else(|e| {
+            panic!(
+                "Couldn't run glib-compile-schemas ({e}). Install it (e.g. libglib2.0-bin \
+                 or glib2-devel), or build with --features json-backend."
+            )
+        });
+
+    if !output.status.success() {
+        panic!(
+            "glib-compile-schemas failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
 }
 
 // Example custom build script.
 fn main() {
     // Tell Cargo that if the given file changes, to rerun this build script.
-    println!("cargo::rerun-if-changed=ui/MainWindow.blp");
+    println!("cargo::rerun-if-changed=data/io.github.eminfedar.vaktisalah-gtk-rs.gschema.xml");
     println!("cargo::rerun-if-changed=build.rs");
-    println!("cargo::rerun-if-changed=Cargo.lock");
 
-    create_pot_file();
+    // Every source of the translatable strings, and the translations
+    println!("cargo::rerun-if-changed=po/POTFILES");
+    println!("cargo::rerun-if-changed=po/LINGUAS");
+    for source in fs::read_to_string("po/POTFILES").unwrap().lines() {
+        println!("cargo::rerun-if-changed={source}");
+    }
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+
+    create_pot_file(&out_dir);
 
     let languages = fs::read("po/LINGUAS").unwrap();
     let languages = String::from_utf8(languages).unwrap();
 
-    update_po_files(&languages);
-    compile_po_files(&languages);
+    for i in languages.split_whitespace() {
+        println!("cargo::rerun-if-changed=po/{i}.po");
+    }
+
+    update_po_files(&languages, &out_dir);
+    compile_po_files(&languages, &out_dir);
+
+    compile_schemas();
 }
@@ -0,0 +1,257 @@
+_vaktisalah__gtk__rs() {
+    local i cur prev opts cmd
+    COMPREPLY=()
+    if [[ "${BASH_VERSINFO[0]}" -ge 4 ]]; then
+        cur="$2"
+    else
+        cur="${COMP_WORDS[COMP_CWORD]}"
+    fi
+    prev="$3"
+    cmd=""
+    opts=""
+
+    for i in "${COMP_WORDS[@]:0:COMP_CWORD}"
+    do
+        case "${cmd},${i}" in
+            ",$1")
+                cmd="vaktisalah__gtk__rs"
+                ;;
+            vaktisalah__gtk__rs,config)
+                cmd="vaktisalah__gtk__rs__subcmd__config"
+                ;;
+            vaktisalah__gtk__rs,month)
+                cmd="vaktisalah__gtk__rs__subcmd__month"
+                ;;
+            vaktisalah__gtk__rs,next)
+                cmd="vaktisalah__gtk__rs__subcmd__next"
+                ;;
+            vaktisalah__gtk__rs,refresh)
+                cmd="vaktisalah__gtk__rs__subcmd__refresh"
+                ;;
+            vaktisalah__gtk__rs,status)
+                cmd="vaktisalah__gtk__rs__subcmd__status"
+                ;;
+            vaktisalah__gtk__rs,today)
+                cmd="vaktisalah__gtk__rs__subcmd__today"
+                ;;
+            vaktisalah__gtk__rs,tomorrow)
+                cmd="vaktisalah__gtk__rs__subcmd__tomorrow"
+                ;;
+            vaktisalah__gtk__rs__subcmd__config,get)
+                cmd="vaktisalah__gtk__rs__subcmd__config__subcmd__get"
+                ;;
+            vaktisalah__gtk__rs__subcmd__config,locations)
+                cmd="vaktisalah__gtk__rs__subcmd__config__subcmd__locations"
+                ;;
+            vaktisalah__gtk__rs__subcmd__config,set)
+                cmd="vaktisalah__gtk__rs__subcmd__config__subcmd__set"
+                ;;
+            *)
+                ;;
+        esac
+    done
+
+    case "${cmd}" in
+        vaktisalah__gtk__rs)
+            opts="-h -V --toggle --background --help --version today tomorrow next month status config refresh"
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 1 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+                return 0
+            fi
+            case "${prev}" in
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+            return 0
+            ;;
+        vaktisalah__gtk__rs__subcmd__config)
+            opts="-h --help get set locations"
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+                return 0
+            fi
+            case "${prev}" in
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+            return 0
+            ;;
+        vaktisalah__gtk__rs__subcmd__config__subcmd__get)
+            opts="-h --help location district-id warn-min play-sound pause-media fullscreen-overlay raise-mode"
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 3 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+                return 0
+            fi
+            case "${prev}" in
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+            return 0
+            ;;
+        vaktisalah__gtk__rs__subcmd__config__subcmd__locations)
+            opts="-h --help"
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 3 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+                return 0
+            fi
+            case "${prev}" in
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+            return 0
+            ;;
+        vaktisalah__gtk__rs__subcmd__config__subcmd__set)
+            opts="-h --help location district-id warn-min play-sound pause-media fullscreen-overlay raise-mode"
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 3 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+                return 0
+            fi
+            case "${prev}" in
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+            return 0
+            ;;
+        vaktisalah__gtk__rs__subcmd__month)
+            opts="-h --date --json --format --help"
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+                return 0
+            fi
+            case "${prev}" in
+                --date)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --format)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+            return 0
+            ;;
+        vaktisalah__gtk__rs__subcmd__next)
+            opts="-h --json --format --help"
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+                return 0
+            fi
+            case "${prev}" in
+                --format)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+            return 0
+            ;;
+        vaktisalah__gtk__rs__subcmd__refresh)
+            opts="-h --help"
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+                return 0
+            fi
+            case "${prev}" in
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+            return 0
+            ;;
+        vaktisalah__gtk__rs__subcmd__status)
+            opts="-h --watch --json --format --help"
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+                return 0
+            fi
+            case "${prev}" in
+                --format)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+            return 0
+            ;;
+        vaktisalah__gtk__rs__subcmd__today)
+            opts="-h --date --json --format --help"
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+                return 0
+            fi
+            case "${prev}" in
+                --date)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --format)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+            return 0
+            ;;
+        vaktisalah__gtk__rs__subcmd__tomorrow)
+            opts="-h --date --json --format --help"
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+                return 0
+            fi
+            case "${prev}" in
+                --date)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --format)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+            return 0
+            ;;
+    esac
+}
+
+_vaktisalah-gtk-rs_locations() {
+    if [[ "${COMP_WORDS[COMP_CWORD-2]}" == "set" && "${COMP_WORDS[COMP_CWORD-1]}" == "location" ]]; then
+        local IFS=$'\n'
+        COMPREPLY=($(compgen -W "$(vaktisalah-gtk-rs config locations 2>/dev/null)" -- "${COMP_WORDS[COMP_CWORD]}"))
+    else
+        _vaktisalah__gtk__rs "$@"
+    fi
+}
+
+if [[ "${BASH_VERSINFO[0]}" -eq 4 && "${BASH_VERSINFO[1]}" -ge 4 || "${BASH_VERSINFO[0]}" -gt 4 ]]; then
+    complete -F _vaktisalah-gtk-rs_locations -o nosort -o bashdefault -o default vaktisalah-gtk-rs
+else
+    complete -F _vaktisalah-gtk-rs_locations -o bashdefault -o default vaktisalah-gtk-rs
+fi
@@ -0,0 +1,65 @@
+# Print an optspec for argparse to handle cmd's options that are independent of any subcommand.
+function __fish_vaktisalah_gtk_rs_global_optspecs
+    string join \n toggle background h/help V/version
+end
+
+function __fish_vaktisalah_gtk_rs_needs_command
+    # Figure out if the current invocation already has a command.
+    set -l cmd (commandline -opc)
+    set -e cmd[1]
+    argparse -s (__fish_vaktisalah_gtk_rs_global_optspecs) -- $cmd 2>/dev/null
+    or return
+    if set -q argv[1]
+        # Also print the command, so this can be used to figure out what it is.
+        echo $argv[1]
+        return 1
+    end
+    return 0
+end
+
+function __fish_vaktisalah_gtk_rs_using_subcommand
+    set -l cmd (__fish_vaktisalah_gtk_rs_needs_command)
+    test -z "$cmd"
+    and return 1
+    contains -- $cmd[1] $argv
+end
+
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_needs_command" -l toggle -d 'Show or hide the window of the running app'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_needs_command" -l background -d 'Start hidden in the tray, with the reminders running'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_needs_command" -s h -l help -d 'Print help (see more with \'--help\')'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_needs_command" -s V -l version -d 'Print version'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_needs_command" -f -a "today" -d 'Prayer times of today, or of --date'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_needs_command" -f -a "tomorrow" -d 'Prayer times of the day after today, or after --date'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_needs_command" -f -a "next" -d 'The next prayer time & the time left until it'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_needs_command" -f -a "month" -d 'Cached prayer times of this month, or of the month of --date'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_needs_command" -f -a "status" -d 'The next prayer & its countdown for status bars, --json is Waybar\'s format'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_needs_command" -f -a "config" -d 'Read or change the location & settings'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_needs_command" -f -a "refresh" -d 'Get the prayer times of the current location again'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_using_subcommand today" -l date -d 'A day other than today' -r
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_using_subcommand today" -l format -d 'Fill a template, e.g. \'{prayer} {time}\'. Days have {date} {hijri} {fajr} {sunrise} {dhuhr} {asr} {maghrib} {isha}, next has {prayer} {key} {date} {time} {remaining} {minutes}, status has {prayer} {key} {time} {remaining} {minutes} {percentage} {class}' -r
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_using_subcommand today" -l json -d 'Print JSON'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_using_subcommand today" -s h -l help -d 'Print help'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_using_subcommand tomorrow" -l date -d 'A day other than today' -r
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_using_subcommand tomorrow" -l format -d 'Fill a template, e.g. \'{prayer} {time}\'. Days have {date} {hijri} {fajr} {sunrise} {dhuhr} {asr} {maghrib} {isha}, next has {prayer} {key} {date} {time} {remaining} {minutes}, status has {prayer} {key} {time} {remaining} {minutes} {percentage} {class}' -r
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_using_subcommand tomorrow" -l json -d 'Print JSON'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_using_subcommand tomorrow" -s h -l help -d 'Print help'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_using_subcommand next" -l format -d 'Fill a template, e.g. \'{prayer} {time}\'. Days have {date} {hijri} {fajr} {sunrise} {dhuhr} {asr} {maghrib} {isha}, next has {prayer} {key} {date} {time} {remaining} {minutes}, status has {prayer} {key} {time} {remaining} {minutes} {percentage} {class}' -r
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_using_subcommand next" -l json -d 'Print JSON'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_using_subcommand next" -s h -l help -d 'Print help'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_using_subcommand month" -l date -d 'A day other than today' -r
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_using_subcommand month" -l format -d 'Fill a template, e.g. \'{prayer} {time}\'. Days have {date} {hijri} {fajr} {sunrise} {dhuhr} {asr} {maghrib} {isha}, next has {prayer} {key} {date} {time} {remaining} {minutes}, status has {prayer} {key} {time} {remaining} {minutes} {percentage} {class}' -r
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_using_subcommand month" -l json -d 'Print JSON'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_using_subcommand month" -s h -l help -d 'Print help'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_using_subcommand status" -l format -d 'Fill a template, e.g. \'{prayer} {time}\'. Days have {date} {hijri} {fajr} {sunrise} {dhuhr} {asr} {maghrib} {isha}, next has {prayer} {key} {date} {time} {remaining} {minutes}, status has {prayer} {key} {time} {remaining} {minutes} {percentage} {class}' -r
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_using_subcommand status" -l watch -d 'Keep printing it every minute, or every second inside the reminders'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_using_subcommand status" -l json -d 'Print JSON'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_using_subcommand status" -s h -l help -d 'Print help'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_using_subcommand config; and not __fish_seen_subcommand_from get set locations" -s h -l help -d 'Print help'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_using_subcommand config; and not __fish_seen_subcommand_from get set locations" -f -a "get" -d 'Print a setting, or all of them'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_using_subcommand config; and not __fish_seen_subcommand_from get set locations" -f -a "set" -d 'Change a setting'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_using_subcommand config; and not __fish_seen_subcommand_from get set locations" -f -a "locations" -d 'Print the cached location names, for the shell completions'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_using_subcommand config; and __fish_seen_subcommand_from get" -s h -l help -d 'Print help (see more with \'--help\')'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_using_subcommand config; and __fish_seen_subcommand_from set" -s h -l help -d 'Print help (see more with \'--help\')'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_using_subcommand config; and __fish_seen_subcommand_from locations" -s h -l help -d 'Print help'
+complete -c vaktisalah-gtk-rs -n "__fish_vaktisalah_gtk_rs_using_subcommand refresh" -s h -l help -d 'Print help'
+complete -c vaktisalah-gtk-rs -n "__fish_seen_subcommand_from config; and __fish_seen_subcommand_from set; and __fish_seen_subcommand_from location" -f -a "(vaktisalah-gtk-rs config locations 2>/dev/null)"
@@ -0,0 +1,117 @@
+// Also included by xtask to generate the shell completions & man page, so it
+// only uses clap.
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+/// Islamic prayer times, from Türkiye's Presidency of Religious Affairs.
+///
+/// Without a command, the window is opened. The commands read the cached prayer
+/// times without a window, e.g. in scripts or SSH sessions.
+#[derive(Debug, Parser)]
+#[command(name = "vaktisalah-gtk-rs", version, disable_help_subcommand = true)]
+pub struct Cli {
+    /// Show or hide the window of the running app
+    #[arg(long)]
+    pub toggle: bool,
+
+    /// Start hidden in the tray, with the reminders running
+    #[arg(long)]
+    pub background: bool,
+
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum CliCommand {
+    #[command(flatten)]
+    Print(PrintCommand),
+    /// The next prayer & its countdown for status bars, --json is Waybar's format
+    Status {
+        /// Keep printing it every minute, or every second inside the reminders
+        #[arg(long)]
+        watch: bool,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Read or change the location & settings
+    #[command(disable_help_subcommand = true)]
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Get the prayer times of the current location again
+    Refresh,
+}
+
+/// Commands that print the cached prayer times once.
+#[derive(Debug, Clone, Subcommand)]
+pub enum PrintCommand {
+    /// Prayer times of today, or of --date
+    Today(DayArgs),
+    /// Prayer times of the day after today, or after --date
+    Tomorrow(DayArgs),
+    /// The next prayer time & the time left until it
+    Next(OutputArgs),
+    /// Cached prayer times of this month, or of the month of --date
+    Month(DayArgs),
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct OutputArgs {
+    /// Print JSON
+    #[arg(long, conflicts_with = "format")]
+    pub json: bool,
+
+    /// Fill a template, e.g. '{prayer} {time}'. Days have {date} {hijri} {fajr} {sunrise}
+    /// {dhuhr} {asr} {maghrib} {isha}, next has {prayer} {key} {date} {time} {remaining}
+    /// {minutes}, status has {prayer} {key} {time} {remaining} {minutes} {percentage} {class}
+    #[arg(long, value_name = "TEMPLATE")]
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct DayArgs {
+    /// A day other than today
+    #[arg(long, value_name = "YYYY-MM-DD")]
+    pub date: Option<String>,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum ConfigAction {
+    /// Print a setting, or all of them
+    Get { key: Option<ConfigKey> },
+    /// Change a setting
+    Set {
+        key: ConfigKey,
+        /// A location is COUNTRY/CITY/DISTRICT, CITY/DISTRICT, a city or district name, or a
+        /// district ID. warn-min is the minutes of the reminder before every prayer, 0 for none
+        value: String,
+    },
+    /// Print the cached location names, for the shell completions
+    #[command(hide = true)]
+    Locations,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ConfigKey {
+    Location,
+    /// Only for get, set it with location
+    DistrictId,
+    WarnMin,
+    PlaySound,
+    PauseMedia,
+    FullscreenOverlay,
+    /// never, always or when-idle
+    RaiseMode,
+}
+
+impl ConfigKey {
+    /// e.g. "warn-min"
+    pub fn name(self) -> String {
+        self.to_possible_value().unwrap().get_name().to_string()
+    }
+}
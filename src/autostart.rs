@@ -0,0 +1,139 @@
+use std::{fs, io, path::PathBuf};
+
+use gettextrs::gettext;
+use gtk::gio;
+use gtk::glib::{self, prelude::*};
+
+use crate::APP_ID;
+
+/// Starts the app hidden in the tray, with the reminders running.
+pub const BACKGROUND_ARG: &str = "--background";
+
+/// Forces the Background portal outside Flatpak, e.g. to try it against a stub portal.
+const PORTAL_ENV: &str = "VAKTISALAH_BACKGROUND_PORTAL";
+
+const PORTAL_NAME: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+
+fn uses_portal() -> bool {
+    fs::exists("/.flatpak-info").unwrap_or(false) || std::env::var_os(PORTAL_ENV).is_some()
+}
+
+/// Runs the app in the background at login, or stops it.
+pub async fn set_run_at_login(enabled: bool) -> Result<(), String> {
+    if uses_portal() {
+        request_background(enabled).await
+    } else {
+        write_autostart_file(enabled).map_err(|e| e.to_string())
+    }
+}
+
+/// `~/.config/autostart/<APP_ID>.desktop`
+fn autostart_file() -> PathBuf {
+    glib::user_config_dir()
+        .join("autostart")
+        .join(format!("{APP_ID}.desktop"))
+}
+
+fn write_autostart_file(enabled: bool) -> io::Result<()> {
+    let path = autostart_file();
+
+    if !enabled {
+        return match fs::remove_file(&path) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        };
+    }
+
+    let exe = std::env::current_exe()?;
+    let entry = format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=Vakt-i Salah\n\
+         Icon={APP_ID}\n\
+         Exec=\"{}\" {BACKGROUND_ARG}\n\
+         X-GNOME-Autostart-enabled=true\n",
+        exe.display()
+    );
+
+    fs::create_dir_all(path.parent().unwrap())?;
+    fs::write(&path, entry)
+}
+
+/// Asks the Background portal, and waits for the user's answer in its `Response` signal.
+async fn request_background(enabled: bool) -> Result<(), String> {
+    let connection = gio::bus_get_future(gio::BusType::Session)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // The request's object path is known in advance from the sender & token
+    let sender = connection
+        .unique_name()
+        .ok_or("Not connected to the session bus")?
+        .trim_start_matches(':')
+        .replace('.', "_");
+    let token = format!("vaktisalah_{}", glib::random_int());
+    let request_path = format!("{PORTAL_PATH}/request/{sender}/{token}");
+
+    let (tx, rx) = async_channel::bounded(1);
+    let subscription = connection.signal_subscribe(
+        Some(PORTAL_NAME),
+        Some("org.freedesktop.portal.Request"),
+        Some("Response"),
+        Some(&request_path),
+        None,
+        gio::DBusSignalFlags::NO_MATCH_RULE,
+        move |_, _, _, _, _, params| {
+            let _ = tx.try_send(params.clone());
+        },
+    );
+
+    let options = glib::VariantDict::new(None);
+    options.insert("handle_token", &token);
+    options.insert(
+        "reason",
+        gettext("Show prayer time notifications after login"),
+    );
+    options.insert("autostart", enabled);
+    options.insert(
+        "commandline",
+        vec![
+            env!("CARGO_PKG_NAME").to_string(),
+            BACKGROUND_ARG.to_string(),
+        ],
+    );
+
+    let called = connection
+        .call_future(
+            Some(PORTAL_NAME),
+            PORTAL_PATH,
+            "org.freedesktop.portal.Background",
+            "RequestBackground",
+            Some(&("", options.end()).to_variant()),
+            None,
+            gio::DBusCallFlags::NONE,
+            -1,
+        )
+        .await;
+
+    let response = match called {
+        Ok(_) => rx.recv().await.map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    connection.signal_unsubscribe(subscription);
+
+    let (code, results) = response?
+        .get::<(u32, glib::VariantDict)>()
+        .ok_or("Invalid response from the Background portal")?;
+
+    if code != 0 {
+        return Err(gettext("Running in the background was not allowed"));
+    }
+
+    let autostart = results.lookup::<bool>("autostart").ok().flatten();
+    if autostart != Some(enabled) {
+        return Err(gettext("Running at login was not allowed"));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,283 @@
+use std::{collections::HashMap, path::Path};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    prayer::{Prayer, PrayerTimesWithDate},
+    preferences::Preferences,
+};
+
+/// Increased when the bundle format changes incompatibly.
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// Preferences that only make sense on the machine they were saved on.
+const LOCAL_KEYS: [&str; 3] = ["window_width", "window_height", "run_at_login"];
+
+/// A portable settings file to roll out the same configuration to other machines.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SettingsBundle {
+    pub version: u32,
+    pub preferences: Preferences,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prayer_times: Option<HashMap<String, PrayerTimesWithDate>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub key: String,
+    pub old: String,
+    pub new: String,
+}
+
+impl SettingsBundle {
+    pub fn new(
+        preferences: &Preferences,
+        prayer_times: Option<HashMap<String, PrayerTimesWithDate>>,
+    ) -> Self {
+        Self {
+            version: BUNDLE_VERSION,
+            preferences: preferences.clone(),
+            prayer_times,
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn from_json(bundle_str: &str) -> Result<Self, String> {
+        let bundle: Self = serde_json::from_str(bundle_str).map_err(|e| e.to_string())?;
+
+        if bundle.version > BUNDLE_VERSION {
+            return Err(format!(
+                "Bundle version {} is newer than the supported version {BUNDLE_VERSION}",
+                bundle.version
+            ));
+        }
+
+        bundle.preferences.migrate();
+        bundle.preferences.validate()?;
+
+        Ok(bundle)
+    }
+
+    /// Imported preferences, keeping the machine specific values of `current`. Sound files
+    /// that don't `exist` on this machine keep the current sound, and are returned.
+    pub fn preferences_for(
+        &self,
+        current: &Preferences,
+        exists: impl Fn(&Path) -> bool,
+    ) -> (Preferences, Vec<Change>) {
+        let preferences = self.preferences.clone();
+
+        preferences
+            .window_width
+            .replace(*current.window_width.borrow());
+        preferences
+            .window_height
+            .replace(*current.window_height.borrow());
+        preferences
+            .run_at_login
+            .replace(*current.run_at_login.borrow());
+
+        let mut skipped_sounds = Vec::new();
+        for (prayer, sound) in Prayer::ALL
+            .iter()
+            .zip(preferences.prayer_sounds.borrow_mut().iter_mut())
+        {
+            // Empty is the bundled sound
+            if sound.is_empty() || exists(Path::new(sound)) {
+                continue;
+            }
+
+            let kept = current
+                .prayer_sounds
+                .borrow()
+                .get(prayer.index())
+                .cloned()
+                .unwrap_or_default();
+
+            skipped_sounds.push(Change {
+                key: format!("prayer_sounds.{}", prayer.key()),
+                old: kept.clone(),
+                new: std::mem::replace(sound, kept),
+            });
+        }
+
+        (preferences, skipped_sounds)
+    }
+}
+
+fn to_map(preferences: &Preferences) -> serde_json::Map<String, Value> {
+    match serde_json::to_value(preferences) {
+        Ok(Value::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    }
+}
+
+fn display_value(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(s)) => s.clone(),
+        Some(v) => v.to_string(),
+        None => "-".to_string(),
+    }
+}
+
+/// Lists the preferences that differ between `old` and `new`.
+pub fn diff(old: &Preferences, new: &Preferences) -> Vec<Change> {
+    let old_map = to_map(old);
+    let new_map = to_map(new);
+
+    new_map
+        .keys()
+        .chain(old_map.keys().filter(|k| !new_map.contains_key(*k)))
+        .filter(|k| !LOCAL_KEYS.contains(&k.as_str()))
+        .filter(|k| old_map.get(*k) != new_map.get(*k))
+        .map(|k| Change {
+            key: k.clone(),
+            old: display_value(old_map.get(k)),
+            new: display_value(new_map.get(k)),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preferences::{tests::defaults, RaiseMode};
+
+    fn exported(preferences: &Preferences) -> String {
+        SettingsBundle::new(preferences, None).to_json().unwrap()
+    }
+
+    #[test]
+    fn from_json_reads_an_exported_file() {
+        let preferences = defaults().preferences;
+        preferences.play_sound.replace(false);
+
+        let bundle = SettingsBundle::from_json(&exported(&preferences)).unwrap();
+
+        assert_eq!(bundle.version, BUNDLE_VERSION);
+        assert_eq!(bundle.preferences, preferences);
+        assert!(bundle.prayer_times.is_none());
+    }
+
+    #[test]
+    fn from_json_rejects_broken_newer_and_invalid_files() {
+        assert!(SettingsBundle::from_json("{").is_err());
+        assert!(SettingsBundle::from_json(r#"{"version": 1}"#).is_err());
+
+        let newer = exported(&defaults().preferences).replace(
+            &format!("\"version\": {BUNDLE_VERSION}"),
+            &format!("\"version\": {}", BUNDLE_VERSION + 1),
+        );
+        let e = SettingsBundle::from_json(&newer).unwrap_err();
+        assert!(e.contains("newer"), "{e}");
+
+        let invalid = defaults().preferences;
+        invalid.set_volume(Prayer::Asr, 1.5);
+        assert!(SettingsBundle::from_json(&exported(&invalid)).is_err());
+    }
+
+    #[test]
+    fn from_json_migrates_older_preferences() {
+        let bundle = SettingsBundle::from_json(
+            r#"{
+                "version": 1,
+                "preferences": {
+                    "country": "TÜRKİYE",
+                    "city": "İSTANBUL",
+                    "district": "İSTANBUL",
+                    "district_id": "9541",
+                    "warning_minutes": 10,
+                    "raise_window": false
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(bundle.preferences.reminders(Prayer::Isha), vec![10]);
+        assert_eq!(*bundle.preferences.raise_mode.borrow(), RaiseMode::Never);
+    }
+
+    #[test]
+    fn diff_lists_only_changed_portable_preferences() {
+        let old = defaults().preferences;
+        let new = defaults().preferences;
+        new.play_sound.replace(false);
+        new.district_id.replace("9206".to_string());
+        new.window_width.replace(800);
+
+        let mut changes = diff(&old, &new);
+        changes.sort_by(|a, b| a.key.cmp(&b.key));
+
+        assert_eq!(
+            changes,
+            vec![
+                Change {
+                    key: "district_id".to_string(),
+                    old: "9541".to_string(),
+                    new: "9206".to_string(),
+                },
+                Change {
+                    key: "play_sound".to_string(),
+                    old: "true".to_string(),
+                    new: "false".to_string(),
+                },
+            ]
+        );
+        assert!(diff(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn preferences_for_keeps_local_values() {
+        let imported = defaults().preferences;
+        imported.window_width.replace(800);
+        imported.window_height.replace(600);
+        imported.run_at_login.replace(true);
+        imported.quiet_hours.replace(true);
+        let bundle = SettingsBundle::new(&imported, None);
+
+        let current = defaults().preferences;
+        let (preferences, skipped_sounds) = bundle.preferences_for(&current, |_| true);
+
+        assert_eq!(preferences.window_width, current.window_width);
+        assert_eq!(preferences.window_height, current.window_height);
+        assert_eq!(preferences.run_at_login, current.run_at_login);
+        assert!(*preferences.quiet_hours.borrow());
+        assert!(skipped_sounds.is_empty());
+    }
+
+    #[test]
+    fn preferences_for_imports_sounds_found_on_this_machine() {
+        let imported = defaults().preferences;
+        imported.set_prayer_sound(Prayer::Fajr, "/usr/share/sounds/adhan.ogg".to_string());
+        imported.set_prayer_sound(Prayer::Dhuhr, "/home/other/adhan.ogg".to_string());
+        let bundle = SettingsBundle::new(&imported, None);
+
+        let current = defaults().preferences;
+        current.set_prayer_sound(Prayer::Dhuhr, "/home/me/dhuhr.ogg".to_string());
+
+        let (preferences, skipped_sounds) =
+            bundle.preferences_for(&current, |path| path.starts_with("/usr/share"));
+
+        assert_eq!(
+            preferences.prayer_sound(Prayer::Fajr),
+            imported.prayer_sound(Prayer::Fajr)
+        );
+        assert_eq!(
+            preferences.prayer_sound(Prayer::Dhuhr),
+            current.prayer_sound(Prayer::Dhuhr)
+        );
+        assert_eq!(
+            skipped_sounds,
+            vec![Change {
+                key: "prayer_sounds.dhuhr".to_string(),
+                old: "/home/me/dhuhr.ogg".to_string(),
+                new: "/home/other/adhan.ogg".to_string(),
+            }]
+        );
+    }
+}
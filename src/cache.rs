@@ -0,0 +1,206 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use chrono::{Local, TimeDelta};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    prayer::PrayerTimesWithDate,
+    preferences,
+    storage::{self, FileSystem, RealFileSystem},
+};
+
+// === CACHE LOADING & SAVING ===
+static CACHE_TEMPLATE: &str = include_str!("../data/cache.json");
+
+/// City & district lists are refreshed after this many days.
+const LOCATIONS_MAX_AGE_DAYS: i64 = 30;
+/// Prayer times are refreshed after this many days even if they still cover today.
+const PRAYER_TIMES_MAX_AGE_DAYS: i64 = 15;
+
+/// Unix timestamps of the last successful fetches. `0` means bundled/unknown.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct FetchedAt {
+    pub locations: i64,
+    pub prayer_times: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CacheJson {
+    pub countries: RefCell<HashMap<String, String>>,
+    pub countries_en: RefCell<HashMap<String, String>>,
+    pub cities: RefCell<HashMap<String, String>>,
+    pub districts: RefCell<HashMap<String, String>>,
+    pub prayer_times: RefCell<HashMap<String, PrayerTimesWithDate>>,
+
+    #[serde(default)]
+    pub fetched_at: RefCell<FetchedAt>,
+}
+
+fn cache_path() -> PathBuf {
+    let mut cache_pathbuf = gtk::glib::user_cache_dir();
+    cache_pathbuf.push("io.github.eminfedar.vaktisalah-gtk-rs/cache.json");
+
+    cache_pathbuf
+}
+
+/// Old versions kept the lists and timetables inside `preferences.json`.
+fn legacy_cache() -> Option<CacheJson> {
+    let preferences_str = fs::read_to_string(preferences::json::path()).ok()?;
+
+    serde_json::from_str(&preferences_str).ok()
+}
+
+impl Default for CacheJson {
+    fn default() -> Self {
+        Self::load(true)
+    }
+}
+
+impl CacheJson {
+    /// Like `default`, but an older cache is left in preferences.json.
+    pub fn read() -> Self {
+        Self::load(false)
+    }
+
+    fn load(move_legacy: bool) -> Self {
+        let cache_pathbuf = cache_path();
+
+        if let Ok(cache_str) = fs::read_to_string(&cache_pathbuf) {
+            match serde_json::from_str(&cache_str) {
+                Ok(r) => return r,
+                Err(e) => {
+                    eprintln!("Couldn't read cache.json: {e}");
+                    eprintln!("Using the bundled cache.");
+                }
+            }
+        } else if let Some(legacy) = legacy_cache() {
+            if move_legacy {
+                eprintln!("Moving cached data out of preferences.json");
+
+                if let Err(e) = legacy.save() {
+                    eprintln!("Couldn't write cache.json: {e}");
+                }
+            }
+
+            return legacy;
+        }
+
+        serde_json::from_str(CACHE_TEMPLATE).unwrap()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        self.save_with(&RealFileSystem, &cache_path())
+    }
+
+    pub fn save_with(&self, fs: &impl FileSystem, path: &Path) -> io::Result<()> {
+        let contents = serde_json::to_string(self)?;
+
+        storage::write_atomic(fs, path, contents.as_bytes())
+    }
+
+    pub fn set_locations(
+        &self,
+        cities: HashMap<String, String>,
+        districts: HashMap<String, String>,
+    ) {
+        self.cities.replace(cities);
+        self.districts.replace(districts);
+        self.fetched_at.borrow_mut().locations = Local::now().timestamp();
+    }
+
+    pub fn set_prayer_times(&self, prayer_times: HashMap<String, PrayerTimesWithDate>) {
+        self.prayer_times.replace(prayer_times);
+        self.fetched_at.borrow_mut().prayer_times = Local::now().timestamp();
+    }
+
+    pub fn is_locations_expired(&self) -> bool {
+        is_expired(self.fetched_at.borrow().locations, LOCATIONS_MAX_AGE_DAYS)
+    }
+
+    pub fn is_prayer_times_expired(&self) -> bool {
+        is_expired(
+            self.fetched_at.borrow().prayer_times,
+            PRAYER_TIMES_MAX_AGE_DAYS,
+        )
+    }
+}
+
+fn is_expired(fetched_at: i64, max_age_days: i64) -> bool {
+    Local::now().timestamp() - fetched_at > TimeDelta::days(max_age_days).num_seconds()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::storage::tests::{FakeFileSystem, Step};
+
+    const PATH: &str = "/cache/cache.json";
+
+    /// Fajr to Isha on every day of `cache_with_times`
+    pub const TIMES: [&str; 6] = ["05:00", "06:30", "12:00", "15:30", "18:00", "19:30"];
+
+    fn bundled() -> CacheJson {
+        serde_json::from_str(CACHE_TEMPLATE).unwrap()
+    }
+
+    /// The bundled lists, with `TIMES` on the days from `first` to `last`.
+    pub fn cache_with_times(first: NaiveDate, last: NaiveDate) -> CacheJson {
+        let cache = bundled();
+
+        let days = first
+            .iter_days()
+            .take_while(|d| *d <= last)
+            .map(|date| {
+                let day = PrayerTimesWithDate {
+                    Imsak: TIMES[0].to_string(),
+                    Gunes: TIMES[1].to_string(),
+                    Ogle: TIMES[2].to_string(),
+                    Ikindi: TIMES[3].to_string(),
+                    Aksam: TIMES[4].to_string(),
+                    Yatsi: TIMES[5].to_string(),
+                    MiladiTarihKisa: date.format("%d.%m.%Y").to_string(),
+                    HicriTarihKisa: "8.4.1448".to_string(),
+                    HicriTarihUzun: "8 Rebiülahir 1448".to_string(),
+                };
+
+                (day.MiladiTarihKisa.clone(), day)
+            })
+            .collect();
+        cache.prayer_times.replace(days);
+
+        cache
+    }
+
+    #[test]
+    fn saved_cache_reads_back() {
+        let fs = FakeFileSystem::default();
+
+        bundled().save_with(&fs, Path::new(PATH)).unwrap();
+
+        let saved = fs.contents(Path::new(PATH)).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<CacheJson>(&saved).unwrap(),
+            bundled()
+        );
+    }
+
+    #[test]
+    fn failed_save_keeps_the_old_cache() {
+        for step in [Step::Write, Step::Fsync, Step::Rename, Step::SyncDir] {
+            let fs = FakeFileSystem::failing_at(step).with_file(Path::new(PATH), b"{}");
+
+            assert!(bundled().save_with(&fs, Path::new(PATH)).is_err());
+
+            if step != Step::SyncDir {
+                assert_eq!(fs.contents(Path::new(PATH)), Some(b"{}".to_vec()));
+            }
+        }
+    }
+}
@@ -0,0 +1,385 @@
+use chrono::{Datelike, Days, Local, NaiveDate, NaiveDateTime};
+use formatx::formatx;
+use gettextrs::gettext;
+use gtk::glib;
+use serde_json::json;
+
+use crate::args::{CliCommand, DayArgs, OutputArgs, PrintCommand};
+use crate::cache::CacheJson;
+use crate::config;
+use crate::prayer::{self, Prayer, PrayerTimesWithDate};
+use crate::statusbar;
+use crate::LOCALE;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Output {
+    Human,
+    Json,
+    /// A formatx template, filled once per day or once for the next prayer
+    Format(String),
+}
+
+impl From<&OutputArgs> for Output {
+    fn from(args: &OutputArgs) -> Self {
+        match &args.format {
+            Some(template) => Output::Format(template.clone()),
+            None if args.json => Output::Json,
+            None => Output::Human,
+        }
+    }
+}
+
+/// `--date`, or today's date at `now`.
+fn date_of(args: &DayArgs, now: NaiveDateTime) -> Result<NaiveDate, String> {
+    let Some(date) = &args.date else {
+        return Ok(now.date());
+    };
+
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| {
+        formatx!(
+            gettext("Invalid date \"{date}\", expected YYYY-MM-DD"),
+            date = date
+        )
+        .unwrap()
+    })
+}
+
+/// Runs a command on the cached prayer times or the settings, without a window or display.
+pub fn run(command: CliCommand) -> glib::ExitCode {
+    let result = match command {
+        CliCommand::Status { watch, output } => return statusbar::run(&(&output).into(), watch),
+        CliCommand::Config { action } => config::run(action),
+        CliCommand::Refresh => config::refresh(),
+        CliCommand::Print(command) => {
+            output(&CacheJson::read(), &command, Local::now().naive_local())
+                .map(|text| println!("{text}"))
+        }
+    };
+
+    match result {
+        Ok(()) => glib::ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            glib::ExitCode::FAILURE
+        }
+    }
+}
+
+/// What `today`, `tomorrow`, `next` & `month` print at `now`.
+pub fn output(
+    cache: &CacheJson,
+    command: &PrintCommand,
+    now: NaiveDateTime,
+) -> Result<String, String> {
+    match command {
+        PrintCommand::Today(args) => day_output(cache, date_of(args, now)?, &(&args.output).into()),
+        PrintCommand::Tomorrow(args) => day_output(
+            cache,
+            date_of(args, now)? + Days::new(1),
+            &(&args.output).into(),
+        ),
+        PrintCommand::Next(output) => next_output(cache, now, &output.into()),
+        PrintCommand::Month(args) => {
+            month_output(cache, date_of(args, now)?, &(&args.output).into())
+        }
+    }
+}
+
+pub fn not_cached(date: NaiveDate) -> String {
+    formatx!(
+        gettext("No prayer times are cached for {date}, open the app to update them"),
+        date = date.format("%Y-%m-%d")
+    )
+    .unwrap()
+}
+
+/// Placeholders of a day, also the fields of its JSON object.
+pub fn day_fields(date: NaiveDate, times: &PrayerTimesWithDate) -> Vec<(&'static str, String)> {
+    let mut fields = vec![
+        ("date", date.format("%Y-%m-%d").to_string()),
+        ("hijri", times.HicriTarihUzun.clone()),
+    ];
+    fields.extend(
+        Prayer::ALL
+            .iter()
+            .map(|p| (p.key(), times.time_of(*p).to_string())),
+    );
+
+    fields
+}
+
+pub fn render(template: &str, fields: &[(&'static str, String)]) -> Result<String, String> {
+    let invalid =
+        |e: formatx::Error| formatx!(gettext("Invalid format: {error}"), error = e).unwrap();
+
+    let template = formatx::Template::new(template).map_err(invalid)?;
+    let mut renderer = template.render();
+    for (name, value) in fields {
+        renderer.named(name, value.as_str());
+    }
+
+    renderer.finish().map_err(invalid)
+}
+
+fn to_json(fields: &[(&'static str, String)]) -> serde_json::Value {
+    fields
+        .iter()
+        .map(|(name, value)| (name.to_string(), json!(value)))
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+/// e.g. "19 October 2026 · 17 Rebiülahir 1448", then a line per prayer.
+pub fn human_day(date: NaiveDate, times: &PrayerTimesWithDate) -> String {
+    let mut lines = vec![format!(
+        "{} · {}",
+        date.format_localized("%d %B %Y", *LOCALE),
+        times.HicriTarihUzun
+    )];
+    lines.extend(
+        Prayer::ALL
+            .iter()
+            .map(|p| format!("{:<10} {}", p.name(), times.time_of(*p))),
+    );
+
+    lines.join("\n")
+}
+
+fn day_output(cache: &CacheJson, date: NaiveDate, output: &Output) -> Result<String, String> {
+    let times = prayer::get_prayers_of_date(cache, date).ok_or_else(|| not_cached(date))?;
+
+    match output {
+        Output::Human => Ok(human_day(date, &times)),
+        Output::Json => Ok(to_json(&day_fields(date, &times)).to_string()),
+        Output::Format(template) => render(template, &day_fields(date, &times)),
+    }
+}
+
+/// The cached days of the month of `date`, in order.
+fn month_output(cache: &CacheJson, date: NaiveDate, output: &Output) -> Result<String, String> {
+    let days: Vec<(NaiveDate, PrayerTimesWithDate)> = date
+        .with_day(1)
+        .unwrap()
+        .iter_days()
+        .take_while(|d| d.month() == date.month())
+        .filter_map(|d| Some((d, prayer::get_prayers_of_date(cache, d)?)))
+        .collect();
+
+    if days.is_empty() {
+        return Err(not_cached(date));
+    }
+
+    match output {
+        Output::Human => Ok(days
+            .iter()
+            .map(|(date, times)| {
+                let columns: Vec<&str> = Prayer::ALL.iter().map(|p| times.time_of(*p)).collect();
+                format!("{}  {}", date.format("%Y-%m-%d"), columns.join("  "))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")),
+        Output::Json => {
+            let days: Vec<_> = days
+                .iter()
+                .map(|(date, times)| to_json(&day_fields(*date, times)))
+                .collect();
+
+            Ok(serde_json::Value::from(days).to_string())
+        }
+        Output::Format(template) => days
+            .iter()
+            .map(|(date, times)| render(template, &day_fields(*date, times)))
+            .collect::<Result<Vec<_>, _>>()
+            .map(|lines| lines.join("\n")),
+    }
+}
+
+/// The first prayer time after `now`, from today's & tomorrow's cached times.
+pub fn next_prayer(cache: &CacheJson, now: NaiveDateTime) -> Option<(Prayer, NaiveDateTime)> {
+    [now.date(), now.date().succ_opt()?]
+        .into_iter()
+        .filter_map(|date| Some((date, prayer::get_prayers_of_date(cache, date)?)))
+        .flat_map(|(date, times)| {
+            Prayer::ALL
+                .into_iter()
+                .filter_map(move |p| Some((p, times.datetime_of(date, p)?)))
+        })
+        .find(|(_, time)| *time > now)
+}
+
+fn next_output(cache: &CacheJson, now: NaiveDateTime, output: &Output) -> Result<String, String> {
+    let (prayer, time) = next_prayer(cache, now).ok_or_else(|| not_cached(now.date()))?;
+
+    // Rounded up, so it's never 0 before the prayer time
+    let minutes = ((time - now).num_seconds() + 59) / 60;
+    let remaining = format!("{}:{:02}", minutes / 60, minutes % 60);
+
+    let fields = vec![
+        ("prayer", prayer.name()),
+        ("key", prayer.key().to_string()),
+        ("date", time.format("%Y-%m-%d").to_string()),
+        ("time", time.format("%H:%M").to_string()),
+        ("remaining", remaining.clone()),
+        ("minutes", minutes.to_string()),
+    ];
+
+    match output {
+        Output::Human => Ok(formatx!(
+            gettext("{prayer} at {time}, in {remaining}"),
+            prayer = prayer.name(),
+            time = time.format("%H:%M"),
+            remaining = remaining
+        )
+        .unwrap()),
+        Output::Json => Ok(json!({
+            "prayer": prayer.key(),
+            "name": prayer.name(),
+            "date": time.format("%Y-%m-%d").to_string(),
+            "time": time.format("%H:%M").to_string(),
+            "remaining_minutes": minutes,
+        })
+        .to_string()),
+        Output::Format(template) => render(template, &fields),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::args::Cli;
+    use crate::cache::tests::cache_with_times;
+
+    /// Runs e.g. `["next", "--json"]` at `now`, with times cached from 2026-10-18 to 2026-10-20.
+    fn print(args: &[&str], now: &str) -> Result<String, String> {
+        let cli = Cli::try_parse_from(["vaktisalah-gtk-rs"].iter().chain(args)).unwrap();
+        let Some(CliCommand::Print(command)) = cli.command else {
+            panic!("{args:?} isn't a print command");
+        };
+
+        let today = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let cache = cache_with_times(today.pred_opt().unwrap(), today.succ_opt().unwrap());
+        let now = NaiveDateTime::parse_from_str(now, "%Y-%m-%d %H:%M:%S").unwrap();
+
+        output(&cache, &command, now)
+    }
+
+    fn print_json(args: &[&str], now: &str) -> serde_json::Value {
+        serde_json::from_str(&print(args, now).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn today_prints_a_line_per_prayer() {
+        let text = print(&["today"], "2026-10-19 11:30:00").unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert!(lines[0].ends_with(" · 8 Rebiülahir 1448"), "{}", lines[0]);
+        assert_eq!(
+            lines[1..],
+            [
+                "Fajr       05:00",
+                "Sunrise    06:30",
+                "Dhuhr      12:00",
+                "Asr        15:30",
+                "Maghrib    18:00",
+                "Isha       19:30",
+            ]
+        );
+    }
+
+    #[test]
+    fn days_print_json_and_templates() {
+        let day = print_json(&["today", "--json"], "2026-10-19 11:30:00");
+        assert_eq!(day["date"], "2026-10-19");
+        assert_eq!(day["hijri"], "8 Rebiülahir 1448");
+        assert_eq!(day["fajr"], "05:00");
+        assert_eq!(day["isha"], "19:30");
+
+        assert_eq!(
+            print(
+                &["tomorrow", "--format", "{date} {maghrib}"],
+                "2026-10-19 11:30:00"
+            ),
+            Ok("2026-10-20 18:00".to_string())
+        );
+        assert_eq!(
+            print(
+                &["today", "--date", "2026-10-18", "--format", "{date}"],
+                "2026-10-19 11:30:00"
+            ),
+            Ok("2026-10-18".to_string())
+        );
+    }
+
+    #[test]
+    fn month_prints_the_cached_days() {
+        assert_eq!(
+            print(
+                &["month", "--format", "{date} {asr}"],
+                "2026-10-19 11:30:00"
+            ),
+            Ok("2026-10-18 15:30\n2026-10-19 15:30\n2026-10-20 15:30".to_string())
+        );
+
+        let days = print_json(&["month", "--json"], "2026-10-19 11:30:00");
+        assert_eq!(days.as_array().map(Vec::len), Some(3));
+        assert_eq!(days[2]["date"], "2026-10-20");
+    }
+
+    #[test]
+    fn uncached_and_invalid_dates_are_errors() {
+        let now = "2026-10-19 11:30:00";
+        let not_cached = |date| {
+            Err(not_cached(
+                NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap(),
+            ))
+        };
+
+        assert_eq!(
+            print(&["today", "--date", "2026-12-01"], now),
+            not_cached("2026-12-01")
+        );
+        assert_eq!(
+            print(&["tomorrow", "--date", "2026-10-20"], now),
+            not_cached("2026-10-21")
+        );
+        assert_eq!(
+            print(&["month", "--date", "2026-11-15"], now),
+            not_cached("2026-11-15")
+        );
+        assert!(print(&["today", "--date", "19.10.2026"], now).is_err());
+        assert!(print(&["today", "--format", "{nope}"], now).is_err());
+    }
+
+    #[test]
+    fn next_counts_down_to_the_next_prayer() {
+        assert_eq!(
+            print(&["next"], "2026-10-19 11:30:20"),
+            Ok("Dhuhr at 12:00, in 0:30".to_string())
+        );
+        assert_eq!(
+            print(
+                &["next", "--format", "{key} {minutes}"],
+                "2026-10-19 11:30:20"
+            ),
+            Ok("dhuhr 30".to_string())
+        );
+    }
+
+    #[test]
+    fn next_after_isha_is_tomorrows_fajr() {
+        let next = print_json(&["next", "--json"], "2026-10-19 23:00:00");
+
+        assert_eq!(next["prayer"], "fajr");
+        assert_eq!(next["date"], "2026-10-20");
+        assert_eq!(next["time"], "05:00");
+        assert_eq!(next["remaining_minutes"], 6 * 60);
+
+        // Tomorrow isn't cached on the last day
+        assert_eq!(
+            print(&["next"], "2026-10-20 23:00:00"),
+            Err(not_cached(NaiveDate::from_ymd_opt(2026, 10, 20).unwrap()))
+        );
+    }
+}
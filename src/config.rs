@@ -0,0 +1,458 @@
+use std::collections::HashMap;
+
+use chrono::Locale;
+use formatx::formatx;
+use gettextrs::gettext;
+use gtk::gio;
+
+use clap::ValueEnum;
+
+use crate::args::{ConfigAction, ConfigKey};
+use crate::cache::CacheJson;
+use crate::dbusservice;
+use crate::networking::{self, LocationLists};
+use crate::prayer::Prayer;
+use crate::preferences::{Location, Preferences, PreferencesJson, RaiseMode, MAX_REMINDER_MINUTES};
+use crate::{LOCALE, RUNTIME};
+
+/// `config get`, `config set` & the location names for the shell completions.
+pub fn run(action: ConfigAction) -> Result<(), String> {
+    match action {
+        ConfigAction::Get { key: None } => get_all(),
+        ConfigAction::Get { key: Some(key) } => get(&key.name()),
+        ConfigAction::Set { key, value } => set(&key.name(), &value),
+        ConfigAction::Locations => print_locations(),
+    }
+}
+
+fn unknown_key(key: &str) -> String {
+    formatx!(gettext("Unknown key \"{key}\""), key = key).unwrap()
+}
+
+/// "30,15" when every prayer has the same reminders before it, e.g. "fajr:30 dhuhr:15" otherwise.
+fn warn_minutes(pref: &Preferences) -> String {
+    let before = |prayer: Prayer| -> String {
+        let minutes: Vec<String> = pref
+            .reminders(prayer)
+            .iter()
+            .filter(|m| **m > 0)
+            .map(i32::to_string)
+            .collect();
+
+        if minutes.is_empty() {
+            "0".to_string()
+        } else {
+            minutes.join(",")
+        }
+    };
+
+    let all: Vec<String> = Prayer::ALL.iter().map(|p| before(*p)).collect();
+    if all.iter().all(|m| *m == all[0]) {
+        return all[0].clone();
+    }
+
+    Prayer::ALL
+        .iter()
+        .zip(all)
+        .map(|(p, m)| format!("{}:{m}", p.key()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn value_of(pref: &Preferences, key: &str) -> Option<String> {
+    let value = match key {
+        "location" => {
+            let l = pref.location();
+            format!("{}/{}/{}", l.country, l.city, l.district)
+        }
+        "district-id" => pref.district_id.borrow().clone(),
+        "warn-min" => warn_minutes(pref),
+        "play-sound" => pref.play_sound.borrow().to_string(),
+        "pause-media" => pref.pause_media.borrow().to_string(),
+        "fullscreen-overlay" => pref.fullscreen_overlay.borrow().to_string(),
+        "raise-mode" => pref.raise_mode.borrow().nick().to_string(),
+        _ => return None,
+    };
+
+    Some(value)
+}
+
+fn get_all() -> Result<(), String> {
+    let pref = PreferencesJson::read()?;
+
+    for key in ConfigKey::value_variants() {
+        let key = key.name();
+        println!("{key} = {}", value_of(&pref.preferences, &key).unwrap());
+    }
+
+    Ok(())
+}
+
+fn get(key: &str) -> Result<(), String> {
+    let pref = PreferencesJson::read()?;
+    let value = value_of(&pref.preferences, key).ok_or_else(|| unknown_key(key))?;
+
+    println!("{value}");
+    Ok(())
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Ok(true),
+        "false" | "no" | "off" | "0" => Ok(false),
+        _ => Err(formatx!(
+            gettext("Expected true or false, got \"{value}\""),
+            value = value
+        )
+        .unwrap()),
+    }
+}
+
+/// Replaces the reminders before every prayer, the ones after the prayer times stay.
+fn set_warn_minutes(pref: &Preferences, value: &str) -> Result<(), String> {
+    let minutes = value
+        .parse::<i32>()
+        .ok()
+        .filter(|m| (0..=MAX_REMINDER_MINUTES).contains(m))
+        .ok_or_else(|| {
+            formatx!(
+                gettext("Expected minutes from 0 to {max}, got \"{value}\""),
+                max = MAX_REMINDER_MINUTES,
+                value = value
+            )
+            .unwrap()
+        })?;
+
+    for prayer in Prayer::ALL {
+        let mut reminders: Vec<i32> = pref
+            .reminders(prayer)
+            .into_iter()
+            .filter(|m| *m < 0)
+            .collect();
+        if minutes > 0 {
+            reminders.push(minutes);
+        }
+
+        pref.set_reminders(prayer, reminders);
+    }
+
+    Ok(())
+}
+
+fn set(key: &str, value: &str) -> Result<(), String> {
+    let pref = PreferencesJson::load()?;
+    let p = &pref.preferences;
+
+    match key {
+        "location" => return set_location(pref, value),
+        "warn-min" => set_warn_minutes(p, value)?,
+        "play-sound" => {
+            p.play_sound.replace(parse_bool(value)?);
+        }
+        "pause-media" => {
+            p.pause_media.replace(parse_bool(value)?);
+        }
+        "fullscreen-overlay" => {
+            p.fullscreen_overlay.replace(parse_bool(value)?);
+        }
+        "raise-mode" => {
+            let mode = RaiseMode::from_nick(value);
+            if mode.nick() != value {
+                return Err(formatx!(
+                    gettext("Expected never, always or when-idle, got \"{value}\""),
+                    value = value
+                )
+                .unwrap());
+            }
+            p.raise_mode.replace(mode);
+        }
+        "district-id" => return Err(gettext("Set the district ID with: config set location ID")),
+        _ => return Err(unknown_key(key)),
+    }
+
+    save(&pref)?;
+    println!("{key} = {}", value_of(p, key).unwrap());
+
+    Ok(())
+}
+
+/// GSettings writes asynchronously, so they are flushed before the process exits.
+fn save(pref: &PreferencesJson) -> Result<(), String> {
+    pref.preferences.validate()?;
+    pref.save().map_err(|e| e.to_string())?;
+    gio::Settings::sync();
+
+    Ok(())
+}
+
+/// Lower case, with "İ" & "ı" the same as "I" & "i", so "istanbul" finds "İSTANBUL"
+/// and "Kadıköy" finds "KADIKÖY".
+fn fold(name: &str) -> String {
+    name.trim()
+        .to_lowercase()
+        .replace('\u{307}', "")
+        .replace('ı', "i")
+}
+
+/// The name & ID of `name` in a name-ID list.
+fn find(list: &HashMap<String, String>, name: &str) -> Option<(String, String)> {
+    let name = fold(name);
+
+    list.iter()
+        .find(|(n, _)| fold(n) == name)
+        .map(|(n, id)| (n.clone(), id.clone()))
+}
+
+/// Turkish or English names are found, and the one the window shows is returned.
+fn find_country(cache: &CacheJson, country: &str) -> Result<(String, String), String> {
+    let (name, id) = find(&cache.countries.borrow(), country)
+        .or_else(|| find(&cache.countries_en.borrow(), country))
+        .ok_or_else(|| formatx!(gettext("Unknown country \"{name}\""), name = country).unwrap())?;
+
+    let shown = if *LOCALE == Locale::tr_TR {
+        cache.countries.borrow()
+    } else {
+        cache.countries_en.borrow()
+    };
+    let name = shown
+        .iter()
+        .find(|(_, i)| **i == id)
+        .map_or(name, |(n, _)| n.clone());
+
+    Ok((name, id))
+}
+
+fn block_on<T>(
+    future: impl std::future::Future<Output = Result<T, reqwest::Error>>,
+) -> Result<T, String> {
+    RUNTIME.block_on(future).map_err(|e| e.to_string())
+}
+
+/// The city & district lists the window would show for `city` of `country`.
+fn location_lists(country_id: &str, city: &str) -> Result<LocationLists, String> {
+    block_on(networking::get_location_lists(country_id, city))
+}
+
+/// Looks a district ID up in the recent locations & the cached districts. Searching
+/// every city of the country would take a request per city, so others need a name.
+fn location_of_id(cache: &CacheJson, pref: &Preferences, id: &str) -> Result<Location, String> {
+    if let Some(location) = pref
+        .recent_locations
+        .borrow()
+        .iter()
+        .find(|l| l.district_id == id)
+    {
+        return Ok(location.clone());
+    }
+
+    if let Some((district, _)) = cache.districts.borrow().iter().find(|(_, d)| *d == id) {
+        return Ok(Location {
+            country: pref.country.borrow().clone(),
+            city: pref.city.borrow().clone(),
+            district: district.clone(),
+            district_id: id.to_string(),
+        });
+    }
+
+    Err(formatx!(
+        gettext("Unknown district ID {id}, set the location by name instead"),
+        id = id
+    )
+    .unwrap())
+}
+
+/// `COUNTRY/CITY/DISTRICT`, `CITY/DISTRICT` in the current country, or a single name
+/// of a city (its central district) or of a district of the current city.
+fn location_of_name(cache: &CacheJson, pref: &Preferences, name: &str) -> Result<Location, String> {
+    let parts: Vec<&str> = name.split('/').map(str::trim).collect();
+    let current_country = pref.country.borrow().clone();
+
+    let (country, city, district) = match parts.as_slice() {
+        [country, city, district] => (*country, Some(*city), *district),
+        [city, district] => (current_country.as_str(), Some(*city), *district),
+        [name] => (current_country.as_str(), None, *name),
+        _ => return Err(gettext("Expected COUNTRY/CITY/DISTRICT")),
+    };
+
+    let (country, country_id) = find_country(cache, country)?;
+    let cities = block_on(networking::get_city_list(&country_id))?;
+
+    let city = match city {
+        Some(city) => Some(city),
+        // A city's name alone means its central district of the same name
+        None if find(&cities, district).is_some() => Some(district),
+        None => None,
+    };
+
+    let (city, city_id) = match city {
+        Some(city) => find(&cities, city)
+            .ok_or_else(|| formatx!(gettext("Unknown city \"{name}\""), name = city).unwrap())?,
+        None => find(&cities, &pref.city.borrow()).ok_or_else(|| {
+            formatx!(
+                gettext("Unknown city or district \"{name}\""),
+                name = district
+            )
+            .unwrap()
+        })?,
+    };
+
+    let districts = block_on(networking::get_district_list(&city_id))?;
+    let (district, district_id) = find(&districts, district).ok_or_else(|| {
+        let mut names: Vec<&str> = districts.keys().map(String::as_str).collect();
+        names.sort_unstable();
+
+        formatx!(
+            gettext("Unknown district \"{name}\" in {city}, expected one of: {districts}"),
+            name = district,
+            city = &city,
+            districts = names.join(", ")
+        )
+        .unwrap()
+    })?;
+
+    Ok(Location {
+        country,
+        city,
+        district,
+        district_id,
+    })
+}
+
+/// Gets the prayer times of `district_id` into the cache.
+fn fetch_prayer_times(cache: &CacheJson, district_id: &str) -> Result<usize, String> {
+    let days = block_on(networking::get_prayer_times(district_id))?;
+    let count = days.len();
+
+    cache.set_prayer_times(
+        days.into_iter()
+            .map(|d| (d.MiladiTarihKisa.clone(), d))
+            .collect(),
+    );
+    cache.save().map_err(|e| e.to_string())?;
+
+    Ok(count)
+}
+
+fn set_location(pref: PreferencesJson, value: &str) -> Result<(), String> {
+    let cache = CacheJson::default();
+    let p = &pref.preferences;
+
+    let location = if value.chars().all(|c| c.is_ascii_digit()) {
+        location_of_id(&cache, p, value)?
+    } else {
+        location_of_name(&cache, p, value)?
+    };
+
+    // Keep the lists of the new city, like the window does
+    let (_, country_id) = find_country(&cache, &location.country)?;
+    let (cities, districts) = location_lists(&country_id, &location.city)?;
+    cache.set_locations(cities, districts);
+
+    fetch_prayer_times(&cache, &location.district_id)?;
+
+    p.set_location(location);
+    p.remember_location();
+    save(&pref)?;
+
+    println!("location = {}", value_of(p, "location").unwrap());
+    println!("district-id = {}", p.district_id.borrow());
+
+    Ok(())
+}
+
+/// Gets the prayer times of the current location, and has a running app get them too.
+pub fn refresh() -> Result<(), String> {
+    let cache = CacheJson::default();
+    let pref = PreferencesJson::load()?;
+    let location = pref.preferences.location();
+
+    let days = fetch_prayer_times(&cache, &location.district_id)?;
+    println!(
+        "{}",
+        formatx!(
+            gettext("Got {days} days of prayer times for {location}"),
+            days = days,
+            location = location.label()
+        )
+        .unwrap()
+    );
+
+    // Its own cache would overwrite ours otherwise
+    if let Ok(connection) = gio::bus_get_sync(gio::BusType::Session, gio::Cancellable::NONE) {
+        let _ = connection.call_sync(
+            Some(dbusservice::BUS_NAME),
+            dbusservice::OBJECT_PATH,
+            dbusservice::INTERFACE,
+            "Refresh",
+            None,
+            None,
+            gio::DBusCallFlags::NO_AUTO_START,
+            -1,
+            gio::Cancellable::NONE,
+        );
+    }
+
+    Ok(())
+}
+
+/// The recent locations, and the cached cities & districts of the current city.
+fn print_locations() -> Result<(), String> {
+    let cache = CacheJson::read();
+    let pref = PreferencesJson::read()?;
+
+    let recent = pref
+        .preferences
+        .recent_locations
+        .borrow()
+        .iter()
+        .map(|l| format!("{}/{}/{}", l.country, l.city, l.district))
+        .collect::<Vec<_>>();
+
+    let mut names: Vec<String> = cache
+        .cities
+        .borrow()
+        .keys()
+        .chain(cache.districts.borrow().keys())
+        .cloned()
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+
+    for name in recent.into_iter().chain(names) {
+        println!("{name}");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fold_ignores_case_and_turkish_dots() {
+        assert_eq!(fold(" İSTANBUL "), "istanbul");
+        assert_eq!(fold("Kadıköy"), "kadiköy");
+        assert_eq!(fold("KADIKÖY"), fold("kadıköy"));
+        assert_eq!(fold("ŞIRNAK"), fold("Şırnak"));
+        assert_ne!(fold("ÜSKÜDAR"), fold("Uskudar"));
+    }
+
+    #[test]
+    fn finds_names_as_typed() {
+        let list: HashMap<String, String> = [("KADIKÖY", "9541"), ("ISPARTA", "9528")]
+            .into_iter()
+            .map(|(n, id)| (n.to_string(), id.to_string()))
+            .collect();
+
+        assert_eq!(
+            find(&list, "Kadıköy"),
+            Some(("KADIKÖY".to_string(), "9541".to_string()))
+        );
+        assert_eq!(
+            find(&list, "ısparta"),
+            Some(("ISPARTA".to_string(), "9528".to_string()))
+        );
+        assert_eq!(find(&list, "Kadikoy"), None);
+    }
+}
@@ -0,0 +1,455 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use gtk::gio;
+use gtk::glib::{self, prelude::*};
+
+use crate::cli;
+use crate::prayer::{Prayer, PrayerTimesWithDate};
+use crate::preferences::Location;
+use crate::scheduler::{Event, EventKind};
+
+/// Well-known name, interface & object of the service
+pub const BUS_NAME: &str = "io.github.eminfedar.VaktiSalah";
+pub const INTERFACE: &str = "io.github.eminfedar.VaktiSalah";
+pub const OBJECT_PATH: &str = "/io/github/eminfedar/VaktiSalah";
+
+/// Properties of the current location
+const LOCATION_PROPERTIES: [&str; 5] = ["Location", "Country", "City", "District", "DistrictId"];
+
+const INTROSPECTION_XML: &str = r#"
+<node>
+  <interface name="io.github.eminfedar.VaktiSalah">
+    <!-- Prayer times of a YYYY-MM-DD date, or of today when it's empty -->
+    <method name="GetTimes">
+      <arg name="date" type="s" direction="in"/>
+      <arg name="times" type="a{ss}" direction="out"/>
+    </method>
+    <method name="GetNext">
+      <arg name="prayer" type="s" direction="out"/>
+      <arg name="date" type="s" direction="out"/>
+      <arg name="time" type="s" direction="out"/>
+      <arg name="remaining_minutes" type="u" direction="out"/>
+    </method>
+    <!-- Gets the prayer times of the current location again -->
+    <method name="Refresh"/>
+
+    <property name="Location" type="s" access="read"/>
+    <property name="Country" type="s" access="read"/>
+    <property name="City" type="s" access="read"/>
+    <property name="District" type="s" access="read"/>
+    <property name="DistrictId" type="s" access="read"/>
+
+    <!-- A reminder, negative minutes are after the prayer time -->
+    <signal name="PrayerWarning">
+      <arg name="prayer" type="s"/>
+      <arg name="minutes" type="i"/>
+    </signal>
+    <signal name="PrayerTime">
+      <arg name="prayer" type="s"/>
+    </signal>
+  </interface>
+</node>
+"#;
+
+/// What the service tells about, so it can be stubbed on a private bus.
+pub trait PrayerSource {
+    fn times_of(&self, date: NaiveDate) -> Option<PrayerTimesWithDate>;
+    /// The first prayer time after `now`
+    fn next_prayer(&self, now: NaiveDateTime) -> Option<(Prayer, NaiveDateTime)>;
+    fn location(&self) -> Location;
+    /// Fails when there is no location to get the prayer times of.
+    fn refresh(&self) -> Result<(), String>;
+}
+
+fn failed(message: &str) -> glib::Error {
+    glib::Error::new(gio::DBusError::Failed, message)
+}
+
+fn invalid_args(message: &str) -> glib::Error {
+    glib::Error::new(gio::DBusError::InvalidArgs, message)
+}
+
+fn call(
+    source: &impl PrayerSource,
+    method: &str,
+    params: &glib::Variant,
+) -> Result<Option<glib::Variant>, glib::Error> {
+    let now = Local::now().naive_local();
+
+    match method {
+        "GetTimes" => {
+            let (date,) = params
+                .get::<(String,)>()
+                .ok_or_else(|| invalid_args("Expected a date"))?;
+            let date = if date.is_empty() {
+                now.date()
+            } else {
+                NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+                    .map_err(|_| invalid_args("Expected a YYYY-MM-DD date"))?
+            };
+
+            let times = source
+                .times_of(date)
+                .ok_or_else(|| failed(&cli::not_cached(date)))?;
+            let fields: HashMap<String, String> = cli::day_fields(date, &times)
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect();
+
+            Ok(Some((fields,).to_variant()))
+        }
+        "GetNext" => {
+            let (prayer, time) = source
+                .next_prayer(now)
+                .ok_or_else(|| failed(&cli::not_cached(now.date())))?;
+            let minutes = ((time - now).num_seconds() + 59) / 60;
+
+            Ok(Some(
+                (
+                    prayer.key(),
+                    time.format("%Y-%m-%d").to_string(),
+                    time.format("%H:%M").to_string(),
+                    minutes as u32,
+                )
+                    .to_variant(),
+            ))
+        }
+        "Refresh" => {
+            source.refresh().map_err(|e| failed(&e))?;
+            Ok(None)
+        }
+        _ => Err(glib::Error::new(
+            gio::DBusError::UnknownMethod,
+            &format!("No such method: {method}"),
+        )),
+    }
+}
+
+fn location_property(location: &Location, name: &str) -> Option<glib::Variant> {
+    let value = match name {
+        "Location" => location.label(),
+        "Country" => location.country.clone(),
+        "City" => location.city.clone(),
+        "District" => location.district.clone(),
+        "DistrictId" => location.district_id.clone(),
+        _ => return None,
+    };
+
+    Some(value.to_variant())
+}
+
+/// Exports the service on `connection`. `source` is asked again on every call, as
+/// the window it reads from may not exist yet.
+pub fn register<S: PrayerSource + 'static>(
+    connection: &gio::DBusConnection,
+    source: impl Fn() -> Option<S> + 'static,
+) -> Result<gio::RegistrationId, glib::Error> {
+    let node = gio::DBusNodeInfo::for_xml(INTROSPECTION_XML)?;
+    let interface = node.lookup_interface(INTERFACE).unwrap();
+
+    let source = Rc::new(source);
+    let property_source = source.clone();
+
+    connection
+        .register_object(OBJECT_PATH, &interface)
+        .method_call(move |_, _, _, _, method, params, invocation| {
+            let result = match source() {
+                Some(source) => call(&source, method, &params),
+                None => Err(failed("The prayer times aren't loaded yet")),
+            };
+
+            invocation.return_result(result);
+        })
+        .property(move |_, _, _, _, name| {
+            property_source()
+                .and_then(|s| location_property(&s.location(), name))
+                .unwrap_or_else(|| "".to_variant())
+        })
+        .build()
+}
+
+/// Also takes the service's well-known name, besides the app's own.
+pub fn own_name(connection: &gio::DBusConnection) -> gio::OwnerId {
+    gio::bus_own_name_on_connection(
+        connection,
+        BUS_NAME,
+        gio::BusNameOwnerFlags::NONE,
+        |_, _| {},
+        |_, name| eprintln!("Couldn't own the D-Bus name {name}"),
+    )
+}
+
+fn emit(connection: &gio::DBusConnection, interface: &str, signal: &str, params: glib::Variant) {
+    if let Err(e) = connection.emit_signal(None, OBJECT_PATH, interface, signal, Some(&params)) {
+        eprintln!("Couldn't emit {signal}: {e}");
+    }
+}
+
+/// `PrayerWarning` for a reminder, `PrayerTime` for a prayer time.
+pub fn emit_event(connection: &gio::DBusConnection, event: &Event) {
+    let prayer = event.prayer.key();
+
+    match event.kind {
+        EventKind::Reminder(minutes) => emit(
+            connection,
+            INTERFACE,
+            "PrayerWarning",
+            (prayer, minutes).to_variant(),
+        ),
+        EventKind::PrayerTime => emit(connection, INTERFACE, "PrayerTime", (prayer,).to_variant()),
+    }
+}
+
+/// `PropertiesChanged` with all the location properties.
+pub fn emit_location_changed(connection: &gio::DBusConnection, location: &Location) {
+    let changed: HashMap<&str, glib::Variant> = LOCATION_PROPERTIES
+        .into_iter()
+        .filter_map(|name| Some((name, location_property(location, name)?)))
+        .collect();
+
+    emit(
+        connection,
+        "org.freedesktop.DBus.Properties",
+        "PropertiesChanged",
+        (INTERFACE, changed, Vec::<String>::new()).to_variant(),
+    );
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::sync::{mpsc, Mutex, MutexGuard, PoisonError};
+    use std::thread;
+
+    use chrono::Days;
+
+    use super::*;
+    use crate::cache::tests::{cache_with_times, TIMES};
+    use crate::cache::CacheJson;
+
+    /// Today & the days around it are cached, a location without ID can't be refreshed.
+    pub struct FakeSource {
+        cache: CacheJson,
+        district_id: &'static str,
+    }
+
+    impl FakeSource {
+        pub fn new(district_id: &'static str) -> Self {
+            let today = Local::now().date_naive();
+
+            Self {
+                cache: cache_with_times(today - Days::new(1), today + Days::new(1)),
+                district_id,
+            }
+        }
+    }
+
+    impl PrayerSource for FakeSource {
+        fn times_of(&self, date: NaiveDate) -> Option<PrayerTimesWithDate> {
+            crate::prayer::get_prayers_of_date(&self.cache, date)
+        }
+
+        fn next_prayer(&self, now: NaiveDateTime) -> Option<(Prayer, NaiveDateTime)> {
+            cli::next_prayer(&self.cache, now)
+        }
+
+        fn location(&self) -> Location {
+            Location {
+                country: "TÜRKİYE".to_string(),
+                city: "İSTANBUL".to_string(),
+                district: "KADIKÖY".to_string(),
+                district_id: self.district_id.to_string(),
+            }
+        }
+
+        fn refresh(&self) -> Result<(), String> {
+            match self.district_id {
+                "" => Err("No district".to_string()),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    /// Starting a private bus points the session bus of the whole process at it, so the
+    /// tests using one take turns.
+    static BUS_TURN: Mutex<()> = Mutex::new(());
+
+    /// A private session bus, taken down when dropped.
+    pub struct PrivateBus {
+        bus: gio::TestDBus,
+        _turn: MutexGuard<'static, ()>,
+    }
+
+    impl PrivateBus {
+        pub fn up() -> Self {
+            // A failed test doesn't hold up the others
+            let turn = BUS_TURN.lock().unwrap_or_else(PoisonError::into_inner);
+
+            let bus = gio::TestDBus::new(gio::TestDBusFlags::NONE);
+            bus.up();
+
+            Self { bus, _turn: turn }
+        }
+
+        pub fn address(&self) -> String {
+            self.bus.bus_address().unwrap().to_string()
+        }
+    }
+
+    impl Drop for PrivateBus {
+        fn drop(&mut self) {
+            self.bus.down();
+        }
+    }
+
+    pub fn connect(address: &str) -> gio::DBusConnection {
+        gio::DBusConnection::for_address_sync(
+            address,
+            gio::DBusConnectionFlags::AUTHENTICATION_CLIENT
+                | gio::DBusConnectionFlags::MESSAGE_BUS_CONNECTION,
+            None,
+            gio::Cancellable::NONE,
+        )
+        .unwrap()
+    }
+
+    /// The service on a private bus, served from its own thread like the app's main loop.
+    struct Service {
+        /// Down when the service is dropped
+        _bus: PrivateBus,
+        client: gio::DBusConnection,
+        name: String,
+    }
+
+    impl Service {
+        fn start(district_id: Option<&'static str>) -> Self {
+            let bus = PrivateBus::up();
+
+            let server = connect(&bus.address());
+            let name = server.unique_name().unwrap().to_string();
+            let (ready, wait_ready) = mpsc::channel();
+
+            thread::spawn(move || {
+                let context = glib::MainContext::new();
+
+                context
+                    .with_thread_default(|| {
+                        register(&server, move || district_id.map(FakeSource::new)).unwrap();
+                        ready.send(()).unwrap();
+
+                        glib::MainLoop::new(Some(&context), false).run();
+                    })
+                    .unwrap();
+            });
+            wait_ready.recv().unwrap();
+
+            Self {
+                client: connect(&bus.address()),
+                _bus: bus,
+                name,
+            }
+        }
+
+        fn call(
+            &self,
+            interface: &str,
+            method: &str,
+            params: Option<glib::Variant>,
+        ) -> Result<glib::Variant, glib::Error> {
+            self.client.call_sync(
+                Some(&self.name),
+                OBJECT_PATH,
+                interface,
+                method,
+                params.as_ref(),
+                None,
+                gio::DBusCallFlags::NONE,
+                -1,
+                gio::Cancellable::NONE,
+            )
+        }
+
+        fn get_times(&self, date: &str) -> Result<HashMap<String, String>, glib::Error> {
+            let reply = self.call(INTERFACE, "GetTimes", Some((date,).to_variant()))?;
+
+            Ok(reply.get::<(HashMap<String, String>,)>().unwrap().0)
+        }
+    }
+
+    #[test]
+    fn gets_the_times_of_a_date() {
+        let service = Service::start(Some("9541"));
+        let today = Local::now().date_naive();
+
+        let times = service.get_times("").unwrap();
+        assert_eq!(times["date"], today.format("%Y-%m-%d").to_string());
+        assert_eq!(times["fajr"], TIMES[0]);
+        assert_eq!(times["isha"], TIMES[5]);
+
+        let tomorrow = (today + Days::new(1)).format("%Y-%m-%d").to_string();
+        assert_eq!(service.get_times(&tomorrow).unwrap()["date"], tomorrow);
+    }
+
+    #[test]
+    fn rejects_invalid_and_uncached_dates() {
+        let service = Service::start(Some("9541"));
+
+        let error = service.get_times("19.10.2026").unwrap_err();
+        assert!(error.matches(gio::DBusError::InvalidArgs), "{error}");
+
+        let error = service.get_times("2000-01-01").unwrap_err();
+        assert!(error.matches(gio::DBusError::Failed), "{error}");
+    }
+
+    #[test]
+    fn gets_the_next_prayer() {
+        let service = Service::start(Some("9541"));
+
+        let reply = service.call(INTERFACE, "GetNext", None).unwrap();
+        let (key, _date, time, minutes) = reply.get::<(String, String, String, u32)>().unwrap();
+
+        assert!(Prayer::ALL.iter().any(|p| p.key() == key), "{key}");
+        assert!(TIMES.contains(&time.as_str()), "{time}");
+        assert!(minutes <= 24 * 60, "{minutes}");
+    }
+
+    #[test]
+    fn refresh_reports_a_missing_location() {
+        assert!(Service::start(Some("9541"))
+            .call(INTERFACE, "Refresh", None)
+            .is_ok());
+
+        let error = Service::start(Some(""))
+            .call(INTERFACE, "Refresh", None)
+            .unwrap_err();
+        assert!(error.matches(gio::DBusError::Failed), "{error}");
+    }
+
+    #[test]
+    fn fails_until_the_prayer_times_are_loaded() {
+        let service = Service::start(None);
+
+        let error = service.call(INTERFACE, "GetNext", None).unwrap_err();
+        assert!(error.matches(gio::DBusError::Failed), "{error}");
+    }
+
+    #[test]
+    fn location_properties() {
+        let service = Service::start(Some("9541"));
+        let get = |name: &str| {
+            let reply = service
+                .call(
+                    "org.freedesktop.DBus.Properties",
+                    "Get",
+                    Some((INTERFACE, name).to_variant()),
+                )
+                .unwrap();
+
+            reply.child_value(0).as_variant().unwrap().get::<String>()
+        };
+
+        assert_eq!(get("Location").as_deref(), Some("KADIKÖY, İSTANBUL"));
+        assert_eq!(get("DistrictId").as_deref(), Some("9541"));
+    }
+}
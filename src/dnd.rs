@@ -0,0 +1,201 @@
+use std::time::Duration;
+
+use gtk::gio;
+use gtk::glib::{self, prelude::*};
+
+const DBUS_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Tells whether the desktop is in Do-Not-Disturb, so it can be stubbed.
+pub trait DndSource {
+    async fn is_enabled(&self) -> bool;
+}
+
+/// Reads Do-Not-Disturb from GNOME through the settings portal, which also works
+/// in Flatpak, or from KDE's notification server.
+#[derive(Debug, Default)]
+pub struct DesktopDnd;
+
+/// Values read through D-Bus may be wrapped in any number of variants.
+fn unwrap_variant(mut value: glib::Variant) -> glib::Variant {
+    while value.type_() == glib::VariantTy::VARIANT {
+        value = value.as_variant().unwrap();
+    }
+
+    value
+}
+
+async fn call(
+    destination: &str,
+    object_path: &str,
+    interface: &str,
+    method: &str,
+    args: glib::Variant,
+) -> Option<glib::Variant> {
+    let connection = gio::bus_get_future(gio::BusType::Session).await.ok()?;
+
+    let reply = connection
+        .call_future(
+            Some(destination),
+            object_path,
+            interface,
+            method,
+            Some(&args),
+            Some(glib::VariantTy::new("(v)").unwrap()),
+            gio::DBusCallFlags::NONE,
+            DBUS_TIMEOUT.as_millis() as i32,
+        )
+        .await
+        .ok()?;
+
+    Some(unwrap_variant(reply.child_value(0)))
+}
+
+impl DesktopDnd {
+    /// GNOME hides banners in Do-Not-Disturb.
+    async fn gnome_show_banners() -> Option<bool> {
+        call(
+            "org.freedesktop.portal.Desktop",
+            "/org/freedesktop/portal/desktop",
+            "org.freedesktop.portal.Settings",
+            "Read",
+            ("org.gnome.desktop.notifications", "show-banners").to_variant(),
+        )
+        .await?
+        .get::<bool>()
+    }
+
+    async fn kde_inhibited() -> Option<bool> {
+        call(
+            "org.freedesktop.Notifications",
+            "/org/freedesktop/Notifications",
+            "org.freedesktop.DBus.Properties",
+            "Get",
+            ("org.freedesktop.Notifications", "Inhibited").to_variant(),
+        )
+        .await?
+        .get::<bool>()
+    }
+}
+
+impl DndSource for DesktopDnd {
+    async fn is_enabled(&self) -> bool {
+        if let Some(show_banners) = Self::gnome_show_banners().await {
+            return !show_banners;
+        }
+
+        Self::kde_inhibited().await.unwrap_or(false)
+    }
+}
+
+/// How an alert is delivered when its notification is shown.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Delivery {
+    /// Play the sound & raise the window
+    pub audible: bool,
+    /// Ask the desktop to show the notification even in Do-Not-Disturb.
+    pub urgent: bool,
+}
+
+/// Silent prayers stay silent. In Do-Not-Disturb, alerts are silent unless the
+/// prayer overrides it, in which case they break through as urgent.
+pub async fn delivery(dnd: &impl DndSource, silent: bool, override_dnd: bool) -> Delivery {
+    if silent {
+        return Delivery {
+            audible: false,
+            urgent: false,
+        };
+    }
+
+    let dnd_enabled = dnd.is_enabled().await;
+
+    Delivery {
+        audible: !dnd_enabled || override_dnd,
+        urgent: dnd_enabled && override_dnd,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct StubDnd {
+        enabled: bool,
+        asked: Cell<bool>,
+    }
+
+    impl DndSource for StubDnd {
+        async fn is_enabled(&self) -> bool {
+            self.asked.set(true);
+            self.enabled
+        }
+    }
+
+    fn deliver(dnd: &StubDnd, silent: bool, override_dnd: bool) -> Delivery {
+        glib::MainContext::new().block_on(delivery(dnd, silent, override_dnd))
+    }
+
+    const AUDIBLE: Delivery = Delivery {
+        audible: true,
+        urgent: false,
+    };
+    const SILENT: Delivery = Delivery {
+        audible: false,
+        urgent: false,
+    };
+
+    #[test]
+    fn audible_without_dnd() {
+        let dnd = StubDnd::default();
+
+        assert_eq!(deliver(&dnd, false, false), AUDIBLE);
+        // Nothing to break through
+        assert_eq!(deliver(&dnd, false, true), AUDIBLE);
+    }
+
+    #[test]
+    fn silent_in_dnd() {
+        let dnd = StubDnd {
+            enabled: true,
+            ..Default::default()
+        };
+
+        assert_eq!(deliver(&dnd, false, false), SILENT);
+    }
+
+    #[test]
+    fn override_breaks_through_dnd() {
+        let dnd = StubDnd {
+            enabled: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            deliver(&dnd, false, true),
+            Delivery {
+                audible: true,
+                urgent: true,
+            }
+        );
+    }
+
+    #[test]
+    fn silent_prayers_dont_ask_the_desktop() {
+        let dnd = StubDnd {
+            enabled: true,
+            ..Default::default()
+        };
+
+        assert_eq!(deliver(&dnd, true, true), SILENT);
+        assert!(!dnd.asked.get());
+    }
+
+    #[test]
+    fn nested_variants_are_unwrapped() {
+        let value = true.to_variant().to_variant().to_variant();
+
+        assert_eq!(unwrap_variant(value).get::<bool>(), Some(true));
+    }
+}
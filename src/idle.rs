@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use gtk::gio;
+use gtk::glib;
+
+use crate::preferences::RaiseMode;
+
+const DBUS_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Without input for this long, the user is considered away from the keyboard.
+pub const IDLE_THRESHOLD: Duration = Duration::from_secs(60);
+
+/// Tells how long the user hasn't used the keyboard or mouse, so it can be stubbed.
+pub trait IdleSource {
+    /// `None` when the desktop doesn't tell.
+    async fn idle_time(&self) -> Option<Duration>;
+}
+
+/// Reads the idle time from GNOME's idle monitor, or from the screensaver
+/// service of KDE & others.
+#[derive(Debug, Default)]
+pub struct DesktopIdle;
+
+async fn call(
+    destination: &str,
+    object_path: &str,
+    interface: &str,
+    method: &str,
+) -> Option<glib::Variant> {
+    let connection = gio::bus_get_future(gio::BusType::Session).await.ok()?;
+
+    let reply = connection
+        .call_future(
+            Some(destination),
+            object_path,
+            interface,
+            method,
+            None,
+            None,
+            gio::DBusCallFlags::NONE,
+            DBUS_TIMEOUT.as_millis() as i32,
+        )
+        .await
+        .ok()?;
+
+    Some(reply.child_value(0))
+}
+
+impl DesktopIdle {
+    async fn gnome_idle_time() -> Option<Duration> {
+        let milliseconds = call(
+            "org.gnome.Mutter.IdleMonitor",
+            "/org/gnome/Mutter/IdleMonitor/Core",
+            "org.gnome.Mutter.IdleMonitor",
+            "GetIdletime",
+        )
+        .await?
+        .get::<u64>()?;
+
+        Some(Duration::from_millis(milliseconds))
+    }
+
+    async fn screensaver_idle_time() -> Option<Duration> {
+        let seconds = call(
+            "org.freedesktop.ScreenSaver",
+            "/org/freedesktop/ScreenSaver",
+            "org.freedesktop.ScreenSaver",
+            "GetSessionIdleTime",
+        )
+        .await?
+        .get::<u32>()?;
+
+        Some(Duration::from_secs(seconds.into()))
+    }
+}
+
+impl IdleSource for DesktopIdle {
+    async fn idle_time(&self) -> Option<Duration> {
+        match Self::gnome_idle_time().await {
+            Some(time) => Some(time),
+            None => Self::screensaver_idle_time().await,
+        }
+    }
+}
+
+/// Whether an alert brings the window to the front. When the idle time is
+/// unknown, the window stays where it is rather than taking the focus.
+pub async fn should_raise(mode: RaiseMode, idle: &impl IdleSource) -> bool {
+    match mode {
+        RaiseMode::Never => false,
+        RaiseMode::Always => true,
+        RaiseMode::WhenIdle => idle.idle_time().await.is_some_and(|t| t >= IDLE_THRESHOLD),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct StubIdle(Option<Duration>);
+
+    impl IdleSource for StubIdle {
+        async fn idle_time(&self) -> Option<Duration> {
+            self.0
+        }
+    }
+
+    fn raises(mode: RaiseMode, idle: Option<Duration>) -> bool {
+        glib::MainContext::new().block_on(should_raise(mode, &StubIdle(idle)))
+    }
+
+    #[test]
+    fn when_idle_waits_for_the_threshold() {
+        assert!(!raises(RaiseMode::WhenIdle, Some(Duration::from_secs(5))));
+        assert!(raises(RaiseMode::WhenIdle, Some(IDLE_THRESHOLD)));
+    }
+
+    #[test]
+    fn unknown_idle_time_doesnt_raise() {
+        assert!(!raises(RaiseMode::WhenIdle, None));
+        assert!(raises(RaiseMode::Always, None));
+        assert!(!raises(RaiseMode::Never, Some(IDLE_THRESHOLD)));
+    }
+}
@@ -1,6 +1,7 @@
 use std::sync::LazyLock;
 
 use chrono::Locale;
+use clap::Parser;
 use gettextrs::{self, bind_textdomain_codeset, bindtextdomain, textdomain};
 
 use adw::prelude::*;
@@ -12,22 +13,40 @@ use tokio::runtime;
 use trayicon::MyTray;
 
 // Crate
+mod args;
+mod autostart;
+mod bundle;
+mod cache;
+mod cli;
+mod config;
 mod current_locale;
+mod dbusservice;
+mod dnd;
+mod idle;
+mod mpris;
 mod networking;
 mod prayer;
+mod prayernotificationrow;
+mod prayeroverlay;
 mod preferences;
 mod rowprayertime;
+mod scheduler;
+mod search;
+mod searchprovider;
 mod sound;
+mod statusbar;
+mod storage;
 mod trayicon;
+mod traypixmap;
 mod window;
 
-use window::MainWindow;
+use window::{install_notification_actions, main_window, MainWindow};
 
 const APP_ID: &str = "io.github.eminfedar.vaktisalah-gtk-rs";
 const LOCALIZATION_DOMAIN_NAME: &str = "vaktisalah-gtk-rs";
 
 static RUNTIME: LazyLock<runtime::Runtime> = LazyLock::new(|| {
-    println!("Runtime initialized");
+    eprintln!("Runtime initialized");
     runtime::Builder::new_multi_thread()
         .worker_threads(4)
         .enable_all()
@@ -49,11 +68,29 @@ static LOCALE: LazyLock<Locale> = LazyLock::new(|| {
 pub enum TrayMessage {
     Activate,
     Exit,
+    /// Silences alerts for this many hours
+    Mute(u32),
+    Unmute,
+    UpdatePrayerTimes,
+    /// District ID of one of the recent locations
+    SwitchLocation(String),
 }
 
 fn main() -> glib::ExitCode {
     setup_localization();
 
+    // Subcommands only read the cache, so they work without a display
+    if let Some(command) = args::Cli::parse().command {
+        return cli::run(command);
+    }
+    eprintln!("Current locale: {}", *LOCALE);
+
+    // The window can't work without them, e.g. without the GSettings schema
+    if let Err(e) = preferences::check() {
+        eprintln!("{e}");
+        return glib::ExitCode::FAILURE;
+    }
+
     // Create a new application
     let app = adw::Application::builder()
         .application_id(APP_ID)
@@ -62,6 +99,8 @@ fn main() -> glib::ExitCode {
 
     app.connect_startup(move |a| {
         load_css();
+        install_notification_actions(a);
+        init_dbus_service(a);
 
         let (tx, rx) = async_channel::bounded(2);
         init_tray(tx);
@@ -69,6 +108,13 @@ fn main() -> glib::ExitCode {
     });
     app.connect_activate(build_ui);
     app.connect_command_line(handle_command_line);
+    app.connect_shutdown(|a| {
+        for window in a.windows() {
+            if let Some(window) = window.downcast_ref::<MainWindow>() {
+                window.flush_pending_save();
+            }
+        }
+    });
 
     // Run the application
     app.run()
@@ -91,8 +137,6 @@ fn setup_localization() {
         }
     }
     bind_textdomain_codeset(LOCALIZATION_DOMAIN_NAME, "UTF-8").unwrap();
-
-    println!("Current locale: {}", *LOCALE);
 }
 
 fn load_css() {
@@ -109,25 +153,25 @@ fn load_css() {
 }
 
 fn handle_command_line(app: &adw::Application, cmd_line: &gio::ApplicationCommandLine) -> i32 {
-    let args = cmd_line.arguments();
-    let args_str: Vec<String> = args
-        .iter()
-        .map(|arg| arg.to_string_lossy().to_string())
-        .collect();
-
-    // Check if --toggle flag is present
-    if args_str.iter().any(|arg| arg == "--toggle") {
+    // main() checked them already, they may be forwarded from another process though
+    let cli = match args::Cli::try_parse_from(cmd_line.arguments()) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("{e}");
+            return e.exit_code();
+        }
+    };
+
+    if cli.toggle {
         // Toggle window visibility
-        let windows = app.windows();
-        if !windows.is_empty() {
-            let window = windows.first().unwrap();
-            if window.is_visible() {
-                window.close();
-            } else {
-                app.activate();
-            }
-        } else {
-            app.activate();
+        match main_window(app) {
+            Some(window) if window.is_visible() => window.close(),
+            _ => app.activate(),
+        }
+    } else if cli.background {
+        // Only start the reminders, a running instance stays as it is
+        if main_window(app).is_none() {
+            create_window(app);
         }
     } else {
         // Normal activation
@@ -137,21 +181,26 @@ fn handle_command_line(app: &adw::Application, cmd_line: &gio::ApplicationComman
     0
 }
 
+/// The window keeps the tick running while hidden, so it is created even in the background.
+fn create_window(app: &adw::Application) -> MainWindow {
+    let window = MainWindow::new(app);
+    window.read_preferences();
+    window.init_second_tick();
+
+    window
+}
+
 fn build_ui(app: &adw::Application) {
     // Create new window and present it
-    if app.windows().is_empty() {
-        let window = MainWindow::new(app);
-        window.read_preferences();
-        window.init_second_tick();
-
-        window.present();
-    } else {
-        let windows = app.windows();
-        let window: &MainWindow = windows.first().unwrap().dynamic_cast_ref().unwrap();
+    let window = match main_window(app) {
+        Some(window) => {
+            window.update_prayer_time_labels();
+            window
+        }
+        None => create_window(app),
+    };
 
-        window.update_prayer_time_labels();
-        window.present();
-    }
+    window.present();
 }
 
 fn handle_tray(receiver: Receiver<TrayMessage>, app: adw::Application) {
@@ -159,17 +208,33 @@ fn handle_tray(receiver: Receiver<TrayMessage>, app: adw::Application) {
         loop {
             match receiver.recv().await {
                 Ok(m) => match m {
-                    TrayMessage::Activate => {
-                        let window_list = app.windows();
-                        let window = window_list.first().unwrap();
-
-                        if window.is_visible() {
-                            window.close();
-                        } else {
-                            app.activate();
+                    TrayMessage::Activate => match main_window(&app) {
+                        Some(window) if window.is_visible() => window.close(),
+                        _ => app.activate(),
+                    },
+                    TrayMessage::Exit => app.quit(),
+                    TrayMessage::Mute(hours) => {
+                        if let Some(window) = main_window(&app) {
+                            window.mute_for(hours);
+                        }
+                    }
+                    TrayMessage::Unmute => {
+                        if let Some(window) = main_window(&app) {
+                            window.unmute();
+                        }
+                    }
+                    TrayMessage::UpdatePrayerTimes => {
+                        if let Some(window) = main_window(&app) {
+                            if let Err(e) = window.update_prayer_times() {
+                                eprintln!("{e}");
+                            }
+                        }
+                    }
+                    TrayMessage::SwitchLocation(district_id) => {
+                        if let Some(window) = main_window(&app) {
+                            window.switch_location(&district_id);
                         }
                     }
-                    TrayMessage::Exit => app.quit(),
                 },
                 Err(e) => {
                     eprintln!("error receiving: {e}");
@@ -180,12 +245,49 @@ fn handle_tray(receiver: Receiver<TrayMessage>, app: adw::Application) {
     });
 }
 
+/// Exports the prayer times & events, and the GNOME Shell search provider, on the
+/// app's session bus connection.
+fn init_dbus_service(app: &adw::Application) {
+    let Some(connection) = app.dbus_connection() else {
+        return;
+    };
+
+    let weak_app = app.downgrade();
+    let registered = dbusservice::register(&connection, move || main_window(&weak_app.upgrade()?));
+
+    match registered {
+        Ok(_) => {
+            dbusservice::own_name(&connection);
+        }
+        Err(e) => eprintln!("Couldn't export the D-Bus service: {e}"),
+    }
+
+    let source_app = app.downgrade();
+    let activate_app = app.downgrade();
+    let registered = searchprovider::register(
+        &connection,
+        move || main_window(&source_app.upgrade()?),
+        move || {
+            if let Some(app) = activate_app.upgrade() {
+                app.activate();
+            }
+        },
+    );
+
+    if let Err(e) = registered {
+        eprintln!("Couldn't export the search provider: {e}");
+    }
+}
+
 fn init_tray(sender: Sender<TrayMessage>) {
     gio::spawn_blocking(move || {
-        let tray = MyTray { sender };
+        let tray = MyTray {
+            sender,
+            status: Default::default(),
+        };
 
-        match tray.spawn_without_dbus_name() {
-            Ok(_) => (),
+        match tray.disable_dbus_name(true).spawn() {
+            Ok(handle) => trayicon::set_handle(handle),
             Err(e) => eprintln!("Tray Icon failed: {e:#?}"),
         }
     });
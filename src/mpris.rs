@@ -0,0 +1,277 @@
+use std::time::Duration;
+
+use gtk::gio;
+use gtk::glib::{self, prelude::*};
+
+const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+const PLAYER_PATH: &str = "/org/mpris/MediaPlayer2";
+const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+
+const DBUS_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Pauses the playing MPRIS media players and resumes only the ones it paused.
+pub struct MediaPauser {
+    connection: Option<gio::DBusConnection>,
+    paused: Vec<String>,
+}
+
+impl MediaPauser {
+    /// Works on any bus, e.g. a private one with a fake player.
+    pub fn new(connection: Option<gio::DBusConnection>) -> Self {
+        Self {
+            connection,
+            paused: Vec::new(),
+        }
+    }
+
+    pub fn session() -> Self {
+        let connection = gio::bus_get_sync(gio::BusType::Session, gio::Cancellable::NONE)
+            .inspect_err(|e| eprintln!("Couldn't connect to the session bus: {e}"))
+            .ok();
+
+        Self::new(connection)
+    }
+
+    fn call(
+        &self,
+        destination: &str,
+        object_path: &str,
+        interface: &str,
+        method: &str,
+        args: Option<glib::Variant>,
+    ) -> Option<glib::Variant> {
+        let connection = self.connection.as_ref()?;
+
+        connection
+            .call_sync(
+                Some(destination),
+                object_path,
+                interface,
+                method,
+                args.as_ref(),
+                None,
+                gio::DBusCallFlags::NONE,
+                DBUS_TIMEOUT.as_millis() as i32,
+                gio::Cancellable::NONE,
+            )
+            .inspect_err(|e| eprintln!("{destination} {method} failed: {e}"))
+            .ok()
+    }
+
+    fn players(&self) -> Vec<String> {
+        let Some(reply) = self.call(
+            "org.freedesktop.DBus",
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus",
+            "ListNames",
+            None,
+        ) else {
+            return Vec::new();
+        };
+
+        let names = reply
+            .get::<(Vec<String>,)>()
+            .map(|(n,)| n)
+            .unwrap_or_default();
+
+        names
+            .into_iter()
+            .filter(|name| name.starts_with(MPRIS_PREFIX))
+            .collect()
+    }
+
+    fn playback_status(&self, player: &str) -> Option<String> {
+        let reply = self.call(
+            player,
+            PLAYER_PATH,
+            "org.freedesktop.DBus.Properties",
+            "Get",
+            Some((PLAYER_INTERFACE, "PlaybackStatus").to_variant()),
+        )?;
+
+        reply.child_value(0).as_variant()?.get::<String>()
+    }
+
+    fn player_call(&self, player: &str, method: &str) -> bool {
+        self.call(player, PLAYER_PATH, PLAYER_INTERFACE, method, None)
+            .is_some()
+    }
+
+    pub fn pause_playing(&mut self) {
+        for player in self.players() {
+            if self.playback_status(&player).as_deref() != Some("Playing") {
+                continue;
+            }
+
+            if self.player_call(&player, "Pause") {
+                eprintln!("Paused {player}");
+                self.paused.push(player);
+            }
+        }
+    }
+
+    /// Players the user started or stopped meanwhile are left alone.
+    pub fn resume(&mut self) {
+        for player in std::mem::take(&mut self.paused) {
+            if self.playback_status(&player).as_deref() == Some("Paused") {
+                self.player_call(&player, "Play");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{mpsc, Arc, Mutex};
+    use std::thread;
+
+    use super::*;
+    use crate::dbusservice::tests::{connect, PrivateBus};
+
+    const PLAYER_XML: &str = r#"
+<node>
+  <interface name="org.mpris.MediaPlayer2.Player">
+    <method name="Play"/>
+    <method name="Pause"/>
+    <property name="PlaybackStatus" type="s" access="read"/>
+  </interface>
+</node>
+"#;
+
+    /// Serves its own main context, as `MediaPauser` blocks on the calls.
+    struct FakePlayer {
+        name: String,
+        status: Arc<Mutex<String>>,
+    }
+
+    impl FakePlayer {
+        fn spawn(bus: &PrivateBus, name: &str, status: &str) -> Self {
+            let address = bus.address();
+            let name = format!("{MPRIS_PREFIX}{name}");
+            let status = Arc::new(Mutex::new(status.to_string()));
+            let (ready, wait_ready) = mpsc::channel();
+
+            let player_name = name.clone();
+            let player_status = status.clone();
+            thread::spawn(move || {
+                let context = glib::MainContext::new();
+
+                context
+                    .with_thread_default(|| {
+                        let connection = connect(&address);
+                        let node = gio::DBusNodeInfo::for_xml(PLAYER_XML).unwrap();
+                        let interface = node.lookup_interface(PLAYER_INTERFACE).unwrap();
+
+                        let method_status = player_status.clone();
+                        connection
+                            .register_object(PLAYER_PATH, &interface)
+                            .method_call(move |_, _, _, _, method, _, invocation| {
+                                let status = if method == "Play" {
+                                    "Playing"
+                                } else {
+                                    "Paused"
+                                };
+                                *method_status.lock().unwrap() = status.to_string();
+
+                                invocation.return_value(None);
+                            })
+                            .property(move |_, _, _, _, _| {
+                                player_status.lock().unwrap().to_variant()
+                            })
+                            .build()
+                            .unwrap();
+
+                        connection
+                            .call_sync(
+                                Some("org.freedesktop.DBus"),
+                                "/org/freedesktop/DBus",
+                                "org.freedesktop.DBus",
+                                "RequestName",
+                                Some(&(player_name, 0u32).to_variant()),
+                                None,
+                                gio::DBusCallFlags::NONE,
+                                -1,
+                                gio::Cancellable::NONE,
+                            )
+                            .unwrap();
+                        ready.send(()).unwrap();
+
+                        glib::MainLoop::new(Some(&context), false).run();
+                    })
+                    .unwrap();
+            });
+            wait_ready.recv().unwrap();
+
+            Self { name, status }
+        }
+
+        fn status(&self) -> String {
+            self.status.lock().unwrap().clone()
+        }
+
+        /// Like the user pressing a button of the player
+        fn set_status(&self, status: &str) {
+            *self.status.lock().unwrap() = status.to_string();
+        }
+    }
+
+    fn pauser(bus: &PrivateBus) -> MediaPauser {
+        MediaPauser::new(Some(connect(&bus.address())))
+    }
+
+    #[test]
+    fn pauses_only_playing_players() {
+        let bus = PrivateBus::up();
+        let playing = FakePlayer::spawn(&bus, "playing", "Playing");
+        let paused = FakePlayer::spawn(&bus, "paused", "Paused");
+        let stopped = FakePlayer::spawn(&bus, "stopped", "Stopped");
+        let mut pauser = pauser(&bus);
+
+        pauser.pause_playing();
+
+        assert_eq!(playing.status(), "Paused");
+        assert_eq!(paused.status(), "Paused");
+        assert_eq!(stopped.status(), "Stopped");
+        assert_eq!(pauser.paused, vec![playing.name]);
+    }
+
+    #[test]
+    fn resumes_only_the_players_it_paused() {
+        let bus = PrivateBus::up();
+        let playing = FakePlayer::spawn(&bus, "playing", "Playing");
+        let paused = FakePlayer::spawn(&bus, "paused", "Paused");
+        let mut pauser = pauser(&bus);
+
+        pauser.pause_playing();
+        pauser.resume();
+
+        assert_eq!(playing.status(), "Playing");
+        assert_eq!(paused.status(), "Paused");
+        assert!(pauser.paused.is_empty());
+    }
+
+    #[test]
+    fn leaves_players_the_user_stopped() {
+        let bus = PrivateBus::up();
+        let first = FakePlayer::spawn(&bus, "first", "Playing");
+        let second = FakePlayer::spawn(&bus, "second", "Playing");
+        let mut pauser = pauser(&bus);
+
+        pauser.pause_playing();
+        second.set_status("Stopped");
+        pauser.resume();
+
+        assert_eq!(first.status(), "Playing");
+        assert_eq!(second.status(), "Stopped");
+    }
+
+    #[test]
+    fn does_nothing_without_a_bus() {
+        let mut pauser = MediaPauser::new(None);
+
+        pauser.pause_playing();
+        pauser.resume();
+
+        assert!(pauser.paused.is_empty());
+    }
+}
@@ -4,6 +4,9 @@ use serde::{Deserialize, Serialize};
 
 use crate::{current_locale, prayer::PrayerTimesWithDate};
 
+/// City & District name-id lists
+pub type LocationLists = (HashMap<String, String>, HashMap<String, String>);
+
 #[allow(non_snake_case)]
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CityResponse {
@@ -23,7 +26,7 @@ pub struct DistrictResponse {
 async fn get_request(endpoint: &str, id: &str) -> Result<reqwest::Response, reqwest::Error> {
     let url = format!("http://ezanvakti.emushaf.net/{}/{}", endpoint, id);
 
-    println!("GET: {url:?}");
+    eprintln!("GET: {url:?}");
 
     reqwest::get(url).await
 }
@@ -77,3 +80,17 @@ pub async fn get_district_list(city_id: &str) -> Result<HashMap<String, String>,
 
     Ok(hm)
 }
+/// Get City & District Lists of a city by its name, for restoring the cached lists
+pub async fn get_location_lists(
+    country_id: &str,
+    city_name: &str,
+) -> Result<LocationLists, reqwest::Error> {
+    let cities = get_city_list(country_id).await?;
+
+    let districts = match cities.get(city_name) {
+        Some(city_id) => get_district_list(city_id).await?,
+        None => HashMap::new(),
+    };
+
+    Ok((cities, districts))
+}
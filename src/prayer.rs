@@ -1,7 +1,7 @@
 use std::fmt::Display;
 
-use crate::preferences::PreferencesJson;
-use chrono::{Days, Local};
+use crate::cache::CacheJson;
+use chrono::{Days, Local, NaiveDate, NaiveDateTime, NaiveTime};
 use gettextrs::gettext;
 use serde::{Deserialize, Serialize};
 
@@ -34,6 +34,46 @@ impl From<u8> for Prayer {
     }
 }
 
+impl Prayer {
+    /// Today's six prayer times, in order
+    pub const ALL: [Prayer; 6] = [
+        Prayer::Fajr,
+        Prayer::Sunrise,
+        Prayer::Dhuhr,
+        Prayer::Asr,
+        Prayer::Maghrib,
+        Prayer::Isha,
+    ];
+
+    /// Index in per-prayer lists, next day's Fajr is the same as Fajr.
+    pub fn index(self) -> usize {
+        (self as usize) % 6
+    }
+
+    /// Untranslated name for scripts, e.g. "fajr"
+    pub fn key(self) -> &'static str {
+        match self {
+            Prayer::Fajr | Prayer::FajrNextDay => "fajr",
+            Prayer::Sunrise => "sunrise",
+            Prayer::Dhuhr => "dhuhr",
+            Prayer::Asr => "asr",
+            Prayer::Maghrib => "maghrib",
+            Prayer::Isha => "isha",
+        }
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            Prayer::Fajr | Prayer::FajrNextDay => gettext("Fajr"),
+            Prayer::Sunrise => gettext("Sunrise"),
+            Prayer::Dhuhr => gettext("Dhuhr"),
+            Prayer::Asr => gettext("Asr"),
+            Prayer::Maghrib => gettext("Maghrib"),
+            Prayer::Isha => gettext("Isha"),
+        }
+    }
+}
+
 impl Display for Prayer {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let p = match self {
@@ -73,28 +113,54 @@ pub struct PrayerTimesWithDate {
     pub HicriTarihUzun: String,
 }
 
-pub fn get_prayers_of_day(
-    preferences: &PreferencesJson,
-    additional_day: u64,
-) -> Option<PrayerTimesWithDate> {
-    let date_formatted = Local::now()
+impl PrayerTimesWithDate {
+    /// "HH:MM" time of `prayer` on this date
+    pub fn time_of(&self, prayer: Prayer) -> &str {
+        match prayer {
+            Prayer::Fajr | Prayer::FajrNextDay => &self.Imsak,
+            Prayer::Sunrise => &self.Gunes,
+            Prayer::Dhuhr => &self.Ogle,
+            Prayer::Asr => &self.Ikindi,
+            Prayer::Maghrib => &self.Aksam,
+            Prayer::Isha => &self.Yatsi,
+        }
+    }
+
+    pub fn datetime_of(&self, date: NaiveDate, prayer: Prayer) -> Option<NaiveDateTime> {
+        let time = NaiveTime::parse_from_str(self.time_of(prayer), "%H:%M").ok()?;
+
+        Some(date.and_time(time))
+    }
+}
+
+pub fn get_prayers_of_day(cache: &CacheJson, additional_day: u64) -> Option<PrayerTimesWithDate> {
+    let date = Local::now()
         .checked_add_days(Days::new(additional_day))?
-        .format("%d.%m.%Y")
-        .to_string();
+        .date_naive();
+
+    get_prayers_of_date(cache, date)
+}
+
+pub fn get_prayers_of_date(cache: &CacheJson, date: NaiveDate) -> Option<PrayerTimesWithDate> {
+    let date_formatted = date.format("%d.%m.%Y").to_string();
 
-    let times = preferences.prayer_times.borrow();
+    let times = cache.prayer_times.borrow();
 
     times.get(&date_formatted).cloned()
 }
 
-pub fn is_prayer_times_valid(preferences: &PreferencesJson) -> bool {
+pub fn is_prayer_times_valid(cache: &CacheJson) -> bool {
+    if cache.is_prayer_times_expired() {
+        return false;
+    }
+
     let today = Local::now();
     let tomorrow = Local::now().checked_add_days(Days::new(5)).unwrap();
 
     let today_formatted = today.format("%d.%m.%Y").to_string();
     let tomorrow_formatted = tomorrow.format("%d.%m.%Y").to_string();
 
-    let times = preferences.prayer_times.borrow();
+    let times = cache.prayer_times.borrow();
 
     if times.get(&today_formatted).is_none() || times.get(&tomorrow_formatted).is_none() {
         return false;
@@ -103,9 +169,11 @@ pub fn is_prayer_times_valid(preferences: &PreferencesJson) -> bool {
     true
 }
 
+/// Time left at `now` until the next prayer of today, or tomorrow's Fajr.
 pub fn calculate_remaining_time(
     todays_prayers: &Option<PrayerTimesWithDate>,
     tomorrows_prayers: &Option<PrayerTimesWithDate>,
+    now: NaiveTime,
 ) -> Option<RemainingTime> {
     let (todays_prayers, tomorrows_prayers) = match (todays_prayers, tomorrows_prayers) {
         (Some(a), Some(b)) => (a, b),
@@ -124,8 +192,6 @@ pub fn calculate_remaining_time(
         &todays_prayers.Yatsi,
         &tomorrows_prayers.Imsak,
     ];
-    let now = Local::now();
-
     for (i, prayer_time) in today_prayer_times_array.iter().enumerate() {
         let mut hours = (prayer_time[0..2]).parse::<i32>().unwrap();
         let minutes = (prayer_time[3..5]).parse::<i32>().unwrap();
@@ -134,7 +200,7 @@ pub fn calculate_remaining_time(
             hours += 24;
         }
 
-        let now_formatted = now.time().format("%H:%M:%S").to_string();
+        let now_formatted = now.format("%H:%M:%S").to_string();
         let now_hours = (now_formatted[0..2]).parse::<i32>().unwrap();
         let now_minutes = (now_formatted[3..5]).parse::<i32>().unwrap();
         let now_seconds = (now_formatted[6..8]).parse::<i32>().unwrap();
@@ -171,3 +237,26 @@ pub fn calculate_remaining_time(
         next_prayer: Prayer::Fajr,
     })
 }
+
+/// The prayer times just before & after `now`, looking at the cached days around it.
+pub fn surrounding_prayer_times(
+    cache: &CacheJson,
+    now: NaiveDateTime,
+) -> Option<(NaiveDateTime, NaiveDateTime)> {
+    let dates = [now.date().pred_opt()?, now.date(), now.date().succ_opt()?];
+
+    let times: Vec<NaiveDateTime> = dates
+        .iter()
+        .filter_map(|date| Some((*date, get_prayers_of_date(cache, *date)?)))
+        .flat_map(|(date, times)| {
+            Prayer::ALL
+                .into_iter()
+                .filter_map(move |prayer| times.datetime_of(date, prayer))
+        })
+        .collect();
+
+    let previous = times.iter().filter(|t| **t <= now).max()?;
+    let next = times.iter().filter(|t| **t > now).min()?;
+
+    Some((*previous, *next))
+}
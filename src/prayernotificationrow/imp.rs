@@ -0,0 +1,65 @@
+use std::cell::{Cell, RefCell};
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gtk::glib;
+
+#[derive(Default, gtk::CompositeTemplate, glib::Properties)]
+#[properties(wrapper_type=super::PrayerNotificationRow)]
+#[template(file = "ui/PrayerNotificationRow.blp")]
+pub struct PrayerNotificationRow {
+    #[template_child]
+    pub sound_row: TemplateChild<adw::ActionRow>,
+
+    #[template_child]
+    pub reset_button: TemplateChild<gtk::Button>,
+
+    #[property(get, set)]
+    pub prayer: Cell<i32>,
+
+    #[property(get, set)]
+    pub notify_before: Cell<bool>,
+
+    #[property(get, set)]
+    pub notify_at_time: Cell<bool>,
+
+    // Path of the sound file, empty for the bundled sound
+    #[property(get, set)]
+    pub sound: RefCell<String>,
+
+    #[property(get, set, minimum = 0.0, maximum = 1.0)]
+    pub volume: Cell<f64>,
+
+    #[property(get, set)]
+    pub fade_in: Cell<bool>,
+
+    #[property(get, set)]
+    pub silent: Cell<bool>,
+
+    #[property(get, set)]
+    pub override_dnd: Cell<bool>,
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for PrayerNotificationRow {
+    const NAME: &'static str = "PrayerNotificationRow";
+    type Type = super::PrayerNotificationRow;
+    type ParentType = adw::ExpanderRow;
+
+    fn class_init(klass: &mut Self::Class) {
+        klass.bind_template();
+        klass.bind_template_instance_callbacks();
+    }
+
+    fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+        obj.init_template();
+    }
+}
+
+#[glib::derived_properties]
+impl ObjectImpl for PrayerNotificationRow {}
+
+impl WidgetImpl for PrayerNotificationRow {}
+impl ListBoxRowImpl for PrayerNotificationRow {}
+impl PreferencesRowImpl for PrayerNotificationRow {}
+impl ExpanderRowImpl for PrayerNotificationRow {}
@@ -0,0 +1,111 @@
+mod imp;
+
+use std::path::PathBuf;
+
+use adw::prelude::*;
+use adw::subclass::prelude::ObjectSubclassIsExt;
+use gettextrs::gettext;
+use gtk::{gio, glib};
+
+use crate::prayer::Prayer;
+use crate::sound::{self, Sound};
+
+glib::wrapper! {
+    pub struct PrayerNotificationRow(ObjectSubclass<imp::PrayerNotificationRow>)
+    @extends adw::ExpanderRow, adw::PreferencesRow, gtk::ListBoxRow, gtk::Widget,
+        @implements gtk::Accessible, gtk::Actionable, gtk::Buildable,
+                    gtk::ConstraintTarget;
+}
+
+fn audio_file_filter() -> gio::ListStore {
+    let filter = gtk::FileFilter::new();
+    filter.set_name(Some(&gettext("Audio Files")));
+    for extension in sound::SUPPORTED_EXTENSIONS {
+        filter.add_suffix(extension);
+    }
+
+    let filters = gio::ListStore::new::<gtk::FileFilter>();
+    filters.append(&filter);
+
+    filters
+}
+
+#[gtk::template_callbacks]
+impl PrayerNotificationRow {
+    #[template_callback]
+    fn on_sound_changed(&self) {
+        let imp = self.imp();
+        let sound = self.sound();
+
+        let subtitle = match PathBuf::from(&sound).file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => gettext("Default"),
+        };
+
+        imp.sound_row.set_subtitle(&subtitle);
+        imp.reset_button.set_sensitive(!sound.is_empty());
+    }
+
+    #[template_callback]
+    fn on_preview_clicked(&self) {
+        // A second click stops the preview
+        if sound::is_playing() {
+            sound::stop();
+            return;
+        }
+
+        let sound = self.sound();
+        let path = (!sound.is_empty()).then(|| PathBuf::from(sound));
+        let default = match Prayer::from(self.prayer() as u8) {
+            Prayer::Sunrise => Sound::Alert,
+            _ => Sound::PrayerTime,
+        };
+
+        sound::play(
+            Sound::custom_or(path, default),
+            self.volume(),
+            self.fade_in(),
+            false,
+        );
+    }
+
+    #[template_callback]
+    fn on_volume_changed(&self) {
+        // Lets the preview follow the slider
+        if sound::is_playing() {
+            sound::set_volume(self.volume());
+        }
+    }
+
+    #[template_callback]
+    fn on_choose_clicked(&self) {
+        let dialog = gtk::FileDialog::builder()
+            .title(gettext("Choose Sound File"))
+            .filters(&audio_file_filter())
+            .modal(true)
+            .build();
+
+        let window = self.root().and_downcast::<gtk::Window>();
+
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = row)]
+            self,
+            async move {
+                let path = match dialog.open_future(window.as_ref()).await {
+                    Ok(file) => file.path(),
+                    // Dismissed
+                    Err(_) => return,
+                };
+
+                if let Some(path) = path {
+                    row.set_sound(path.to_string_lossy().to_string());
+                }
+            }
+        ));
+    }
+
+    #[template_callback]
+    fn on_reset_clicked(&self) {
+        self.set_sound(String::new());
+    }
+}
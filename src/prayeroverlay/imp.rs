@@ -0,0 +1,63 @@
+use std::cell::{Cell, RefCell};
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use chrono::NaiveDateTime;
+use gtk::{glib, pango};
+
+#[derive(Default, gtk::CompositeTemplate, glib::Properties)]
+#[properties(wrapper_type=super::PrayerOverlay)]
+#[template(file = "ui/PrayerOverlay.blp")]
+pub struct PrayerOverlay {
+    #[template_child]
+    pub countdown_label: TemplateChild<gtk::Label>,
+
+    #[property(get, set)]
+    pub heading: RefCell<String>,
+
+    #[property(get, set)]
+    pub body: RefCell<String>,
+
+    #[property(get, set)]
+    pub countdown: RefCell<String>,
+
+    pub prayer_time: Cell<Option<NaiveDateTime>>,
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for PrayerOverlay {
+    const NAME: &'static str = "PrayerOverlay";
+    type Type = super::PrayerOverlay;
+    type ParentType = adw::Window;
+
+    fn class_init(klass: &mut Self::Class) {
+        klass.bind_template();
+        klass.bind_template_instance_callbacks();
+        klass.add_binding_action(
+            gtk::gdk::Key::Escape,
+            gtk::gdk::ModifierType::empty(),
+            "window.close",
+        );
+    }
+
+    fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+        obj.init_template();
+    }
+}
+
+#[glib::derived_properties]
+impl ObjectImpl for PrayerOverlay {
+    fn constructed(&self) {
+        self.parent_constructed();
+
+        // Readable from across the room
+        let attributes = pango::AttrList::new();
+        attributes.insert(pango::AttrFloat::new_scale(6.0));
+        attributes.insert(pango::AttrInt::new_weight(pango::Weight::Bold));
+        self.countdown_label.set_attributes(Some(&attributes));
+    }
+}
+
+impl WidgetImpl for PrayerOverlay {}
+impl WindowImpl for PrayerOverlay {}
+impl AdwWindowImpl for PrayerOverlay {}
@@ -0,0 +1,78 @@
+mod imp;
+
+use std::time::Duration;
+
+use adw::prelude::*;
+use adw::subclass::prelude::ObjectSubclassIsExt;
+use chrono::{Local, NaiveDateTime};
+use gtk::glib;
+
+use crate::sound;
+
+glib::wrapper! {
+    pub struct PrayerOverlay(ObjectSubclass<imp::PrayerOverlay>)
+    @extends adw::Window, gtk::Window, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget,
+                    gtk::Native, gtk::Root, gtk::ShortcutManager;
+}
+
+/// e.g. "00:14:59" before the prayer time, the prayer time itself after it.
+fn countdown_text(prayer_time: NaiveDateTime, now: NaiveDateTime) -> String {
+    let remaining = (prayer_time - now).num_seconds();
+
+    if remaining <= 0 {
+        return prayer_time.format("%H:%M").to_string();
+    }
+
+    format!(
+        "{:0>2}:{:0>2}:{:0>2}",
+        remaining / 3600,
+        remaining % 3600 / 60,
+        remaining % 60
+    )
+}
+
+#[gtk::template_callbacks]
+impl PrayerOverlay {
+    /// A fullscreen window counting down to `prayer_time`, until it is dismissed.
+    pub fn new(
+        app: &gtk::Application,
+        heading: &str,
+        body: &str,
+        prayer_time: NaiveDateTime,
+    ) -> Self {
+        let overlay: Self = glib::Object::builder()
+            .property("application", app)
+            .property("heading", heading)
+            .property("body", body)
+            .build();
+
+        overlay.imp().prayer_time.set(Some(prayer_time));
+        overlay.update_countdown();
+
+        let overlay_clone = overlay.downgrade();
+        glib::timeout_add_local(Duration::from_secs(1), move || {
+            match overlay_clone.upgrade() {
+                Some(overlay) => {
+                    overlay.update_countdown();
+                    glib::ControlFlow::Continue
+                }
+                None => glib::ControlFlow::Break,
+            }
+        });
+
+        overlay
+    }
+
+    fn update_countdown(&self) {
+        if let Some(prayer_time) = self.imp().prayer_time.get() {
+            self.set_countdown(countdown_text(prayer_time, Local::now().naive_local()));
+        }
+    }
+
+    #[template_callback]
+    fn on_dismiss_clicked(&self) {
+        sound::stop();
+        self.close();
+    }
+}
@@ -0,0 +1,285 @@
+use std::io;
+
+use gtk::gio::{self, prelude::*};
+use gtk::glib;
+
+use super::{json, Location, Preferences, PreferencesJson, RaiseMode};
+use crate::{prayer::Prayer, APP_ID};
+
+/// Opens the app's settings. Falls back to the schema compiled by `build.rs`
+/// when it is not installed system-wide (e.g. `cargo run`).
+pub fn settings() -> Result<gio::Settings, String> {
+    let default_source = gio::SettingsSchemaSource::default();
+
+    let schema = default_source
+        .as_ref()
+        .and_then(|s| s.lookup(APP_ID, true))
+        .or_else(|| {
+            gio::SettingsSchemaSource::from_directory(
+                concat!(env!("OUT_DIR"), "/schemas"),
+                default_source.as_ref(),
+                false,
+            )
+            .ok()?
+            .lookup(APP_ID, false)
+        })
+        .ok_or_else(|| {
+            format!(
+                "The GSettings schema {APP_ID} isn't installed. Copy \
+                 data/{APP_ID}.gschema.xml to /usr/share/glib-2.0/schemas/ and run \
+                 glib-compile-schemas there, or build with --features json-backend."
+            )
+        })?;
+
+    Ok(gio::Settings::new_full(
+        &schema,
+        None::<&gio::SettingsBackend>,
+        None,
+    ))
+}
+
+/// Whether the settings can be opened at all.
+pub fn check() -> Result<(), String> {
+    settings().map(drop)
+}
+
+pub fn load() -> Result<PreferencesJson, String> {
+    let settings = settings()?;
+
+    // Move the values of older versions' preferences.json only once
+    if !settings.boolean("json-migrated") {
+        if let Some(old) = json::read() {
+            eprintln!("Migrating preferences.json to GSettings");
+
+            if let Err(e) = write(&settings, &old.preferences) {
+                eprintln!("Couldn't migrate preferences.json: {e}");
+            }
+        }
+
+        if let Err(e) = settings.set_boolean("json-migrated", true) {
+            eprintln!("Couldn't mark preferences.json as migrated: {e}");
+        }
+    }
+
+    migrate_warning_minutes(&settings);
+    migrate_raise_window(&settings);
+
+    Ok(read(&settings))
+}
+
+/// Like `load`, but the migrations only change the returned preferences.
+pub fn read_only() -> Result<PreferencesJson, String> {
+    let settings = settings()?;
+
+    if !settings.boolean("json-migrated") {
+        if let Some(old) = json::read() {
+            return Ok(old);
+        }
+    }
+
+    let preferences = read(&settings);
+    let p = &preferences.preferences;
+    if let Some(reminders) = legacy_reminders(&settings) {
+        p.reminders.replace(reminders);
+    }
+    if let Some(mode) = legacy_raise_mode(&settings) {
+        p.raise_mode.replace(mode);
+    }
+
+    Ok(preferences)
+}
+
+/// Reminders for every prayer from the single `warning-minutes` of older versions.
+fn legacy_reminders(settings: &gio::Settings) -> Option<Vec<Vec<i32>>> {
+    if settings.user_value("warning-minutes").is_none()
+        || settings.user_value("reminders").is_some()
+    {
+        return None;
+    }
+
+    let minutes = settings.uint("warning-minutes") as i32;
+
+    Some(vec![vec![minutes]; Prayer::ALL.len()])
+}
+
+fn migrate_warning_minutes(settings: &gio::Settings) {
+    let Some(reminders) = legacy_reminders(settings) else {
+        return;
+    };
+
+    if let Err(e) = settings.set("reminders", reminders) {
+        eprintln!("Couldn't migrate warning-minutes: {e}");
+    }
+    settings.reset("warning-minutes");
+}
+
+/// The raise mode from the `raise-window` switch of older versions.
+fn legacy_raise_mode(settings: &gio::Settings) -> Option<RaiseMode> {
+    if settings.user_value("raise-window").is_none() || settings.user_value("raise-mode").is_some()
+    {
+        return None;
+    }
+
+    if settings.boolean("raise-window") {
+        Some(RaiseMode::Always)
+    } else {
+        Some(RaiseMode::Never)
+    }
+}
+
+fn migrate_raise_window(settings: &gio::Settings) {
+    if settings.user_value("raise-window").is_none() {
+        return;
+    }
+
+    if let Some(mode) = legacy_raise_mode(settings) {
+        if let Err(e) = settings.set_string("raise-mode", mode.nick()) {
+            eprintln!("Couldn't migrate raise-window: {e}");
+        }
+    }
+    settings.reset("raise-window");
+}
+
+/// Like `json::parse`, rejects values that can't come from the UI (e.g. set with `gsettings`).
+pub fn reload() -> Result<PreferencesJson, String> {
+    let preferences = read(&settings()?);
+    preferences.preferences.validate()?;
+
+    Ok(preferences)
+}
+
+pub fn watch(f: impl Fn() + 'static) -> Option<glib::Object> {
+    let settings = match settings() {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Couldn't watch the settings: {e}");
+            return None;
+        }
+    };
+
+    settings.connect_changed(None, move |_, _| f());
+
+    Some(settings.upcast())
+}
+
+pub fn save(preferences: &PreferencesJson) -> io::Result<()> {
+    let settings = settings().map_err(io::Error::other)?;
+
+    write(&settings, &preferences.preferences).map_err(io::Error::other)
+}
+
+pub fn read(settings: &gio::Settings) -> PreferencesJson {
+    let preferences = Preferences {
+        country: settings.string("country").to_string().into(),
+        city: settings.string("city").to_string().into(),
+        district: settings.string("district").to_string().into(),
+        district_id: settings.string("district-id").to_string().into(),
+        recent_locations: settings
+            .get::<Vec<(String, String, String, String)>>("recent-locations")
+            .into_iter()
+            .map(|(country, city, district, district_id)| Location {
+                country,
+                city,
+                district,
+                district_id,
+            })
+            .collect::<Vec<_>>()
+            .into(),
+        warning_minutes: None.into(),
+
+        play_sound: settings.boolean("play-sound").into(),
+        raise_window: None.into(),
+        raise_mode: RaiseMode::from_nick(&settings.string("raise-mode")).into(),
+        fullscreen_overlay: settings.boolean("fullscreen-overlay").into(),
+        run_at_login: settings.boolean("run-at-login").into(),
+        pause_media: settings.boolean("pause-media").into(),
+
+        notify_before: settings.get::<Vec<bool>>("notify-before").into(),
+        notify_at_time: settings.get::<Vec<bool>>("notify-at-time").into(),
+        reminders: settings.get::<Vec<Vec<i32>>>("reminders").into(),
+        prayer_sounds: settings.get::<Vec<String>>("prayer-sounds").into(),
+        volumes: settings.get::<Vec<f64>>("volumes").into(),
+        fade_in: settings.get::<Vec<bool>>("fade-in").into(),
+        silent: settings.get::<Vec<bool>>("silent").into(),
+        override_dnd: settings.get::<Vec<bool>>("override-dnd").into(),
+
+        quiet_hours: settings.boolean("quiet-hours").into(),
+        quiet_hours_start: settings.uint("quiet-hours-start").into(),
+        quiet_hours_end: settings.uint("quiet-hours-end").into(),
+
+        window_width: settings.int("window-width").into(),
+        window_height: settings.int("window-height").into(),
+    };
+
+    PreferencesJson { preferences }
+}
+
+/// Only changed keys are written, so saving doesn't emit needless `changed` signals.
+fn set_if_changed(
+    settings: &gio::Settings,
+    key: &str,
+    value: impl Into<glib::Variant>,
+) -> Result<(), glib::BoolError> {
+    let value = value.into();
+
+    if settings.value(key) != value {
+        settings.set_value(key, &value)?;
+    }
+
+    Ok(())
+}
+
+fn write(settings: &gio::Settings, p: &Preferences) -> Result<(), glib::BoolError> {
+    set_if_changed(settings, "country", p.country.borrow().as_str())?;
+    set_if_changed(settings, "city", p.city.borrow().as_str())?;
+    set_if_changed(settings, "district", p.district.borrow().as_str())?;
+    set_if_changed(settings, "district-id", p.district_id.borrow().as_str())?;
+    set_if_changed(
+        settings,
+        "recent-locations",
+        p.recent_locations
+            .borrow()
+            .iter()
+            .map(|l| {
+                (
+                    l.country.clone(),
+                    l.city.clone(),
+                    l.district.clone(),
+                    l.district_id.clone(),
+                )
+            })
+            .collect::<Vec<_>>(),
+    )?;
+
+    set_if_changed(settings, "play-sound", *p.play_sound.borrow())?;
+    set_if_changed(settings, "raise-mode", p.raise_mode.borrow().nick())?;
+    set_if_changed(
+        settings,
+        "fullscreen-overlay",
+        *p.fullscreen_overlay.borrow(),
+    )?;
+    set_if_changed(settings, "run-at-login", *p.run_at_login.borrow())?;
+    set_if_changed(settings, "pause-media", *p.pause_media.borrow())?;
+
+    set_if_changed(settings, "notify-before", p.notify_before.borrow().clone())?;
+    set_if_changed(
+        settings,
+        "notify-at-time",
+        p.notify_at_time.borrow().clone(),
+    )?;
+    set_if_changed(settings, "reminders", p.reminders.borrow().clone())?;
+    set_if_changed(settings, "prayer-sounds", p.prayer_sounds.borrow().clone())?;
+    set_if_changed(settings, "volumes", p.volumes.borrow().clone())?;
+    set_if_changed(settings, "fade-in", p.fade_in.borrow().clone())?;
+    set_if_changed(settings, "silent", p.silent.borrow().clone())?;
+    set_if_changed(settings, "override-dnd", p.override_dnd.borrow().clone())?;
+
+    set_if_changed(settings, "quiet-hours", *p.quiet_hours.borrow())?;
+    set_if_changed(settings, "quiet-hours-start", *p.quiet_hours_start.borrow())?;
+    set_if_changed(settings, "quiet-hours-end", *p.quiet_hours_end.borrow())?;
+
+    set_if_changed(settings, "window-width", *p.window_width.borrow())?;
+    set_if_changed(settings, "window-height", *p.window_height.borrow())?;
+
+    Ok(())
+}
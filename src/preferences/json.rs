@@ -0,0 +1,154 @@
+use std::path::PathBuf;
+
+use super::PreferencesJson;
+
+pub fn path() -> PathBuf {
+    let mut preferences_pathbuf = gtk::glib::user_config_dir();
+    preferences_pathbuf.push("io.github.eminfedar.vaktisalah-gtk-rs/preferences.json");
+
+    preferences_pathbuf
+}
+
+/// Parses and validates the contents of a `preferences.json` file.
+pub fn parse(preferences_str: &str) -> Result<PreferencesJson, String> {
+    let preferences: PreferencesJson =
+        serde_json::from_str(preferences_str).map_err(|e| e.to_string())?;
+
+    preferences.preferences.migrate();
+    preferences.preferences.validate()?;
+
+    Ok(preferences)
+}
+
+/// Reads `preferences.json` if it exists and is valid.
+pub fn read() -> Option<PreferencesJson> {
+    let preferences_str = std::fs::read_to_string(path()).ok()?;
+
+    match parse(&preferences_str) {
+        Ok(r) => Some(r),
+        Err(e) => {
+            eprintln!("Couldn't read preferences.json: {e}");
+            None
+        }
+    }
+}
+
+#[cfg(feature = "json-backend")]
+pub use backend::*;
+
+#[cfg(feature = "json-backend")]
+mod backend {
+    use std::io;
+
+    use gtk::gio::{self, prelude::*};
+    use gtk::glib;
+
+    use super::{parse, path, read, PreferencesJson};
+    use crate::storage::{self, FileSystem, RealFileSystem};
+
+    // === PREFERENCE LOADING & SAVING ===
+    static PREFERENCES_TEMPLATE: &str = include_str!("../../data/preferences.json");
+
+    pub fn template() -> PreferencesJson {
+        parse(PREFERENCES_TEMPLATE).unwrap()
+    }
+
+    /// preferences.json can always be used, a missing or broken one is replaced.
+    pub fn check() -> Result<(), String> {
+        Ok(())
+    }
+
+    pub fn load() -> Result<PreferencesJson, String> {
+        let preferences_pathbuf = path();
+
+        if preferences_pathbuf.exists() {
+            Ok(read().unwrap_or_else(|| {
+                eprintln!("Using the default preferences.");
+                template()
+            }))
+        } else {
+            if let Err(e) = storage::write_atomic(
+                &RealFileSystem,
+                &preferences_pathbuf,
+                PREFERENCES_TEMPLATE.as_bytes(),
+            ) {
+                eprintln!("Couldn't create preferences.json: {e}");
+            }
+
+            Ok(template())
+        }
+    }
+
+    /// Like `load`, but a missing preferences.json isn't created.
+    pub fn read_only() -> Result<PreferencesJson, String> {
+        Ok(read().unwrap_or_else(template))
+    }
+
+    pub fn reload() -> Result<PreferencesJson, String> {
+        let preferences_str = std::fs::read_to_string(path()).map_err(|e| e.to_string())?;
+
+        parse(&preferences_str)
+    }
+
+    pub fn watch(f: impl Fn() + 'static) -> Option<glib::Object> {
+        let file = gio::File::for_path(path());
+
+        let monitor =
+            match file.monitor_file(gio::FileMonitorFlags::WATCH_MOVES, gio::Cancellable::NONE) {
+                Ok(m) => m,
+                Err(e) => {
+                    eprintln!("Couldn't watch preferences.json: {e}");
+                    return None;
+                }
+            };
+
+        // Atomic writes show up as renames, editors may only send a hint at the end
+        monitor.connect_changed(move |_, _, _, event| match event {
+            gio::FileMonitorEvent::ChangesDoneHint
+            | gio::FileMonitorEvent::Created
+            | gio::FileMonitorEvent::MovedIn
+            | gio::FileMonitorEvent::Renamed => f(),
+            _ => (),
+        });
+
+        Some(monitor.upcast())
+    }
+
+    pub fn save(preferences: &PreferencesJson) -> io::Result<()> {
+        save_with(preferences, &RealFileSystem)
+    }
+
+    pub fn save_with(preferences: &PreferencesJson, fs: &impl FileSystem) -> io::Result<()> {
+        let contents = serde_json::to_string(preferences)?;
+
+        storage::write_atomic(fs, &path(), contents.as_bytes())
+    }
+}
+
+#[cfg(all(test, feature = "json-backend"))]
+mod tests {
+    use super::*;
+    use crate::storage::tests::{FakeFileSystem, Step};
+
+    #[test]
+    fn saved_preferences_read_back() {
+        let fs = FakeFileSystem::default();
+
+        save_with(&template(), &fs).unwrap();
+
+        let saved = String::from_utf8(fs.contents(&path()).unwrap()).unwrap();
+        assert_eq!(parse(&saved).unwrap(), template());
+    }
+
+    #[test]
+    fn failed_save_keeps_the_old_preferences() {
+        for step in [Step::Write, Step::Fsync, Step::Rename] {
+            let fs = FakeFileSystem::failing_at(step).with_file(&path(), b"old");
+
+            let e = save_with(&template(), &fs).unwrap_err();
+
+            assert_eq!(e.to_string(), format!("{step:?} failed"));
+            assert_eq!(fs.contents(&path()), Some(b"old".to_vec()));
+        }
+    }
+}
@@ -0,0 +1,519 @@
+use std::{cell::RefCell, io, path::PathBuf};
+
+use chrono::{NaiveTime, Timelike};
+use gtk::glib;
+use serde::{Deserialize, Serialize};
+
+use crate::prayer::Prayer;
+
+#[cfg(not(feature = "json-backend"))]
+pub mod gsettings;
+pub mod json;
+
+#[cfg(not(feature = "json-backend"))]
+use gsettings as backend;
+#[cfg(feature = "json-backend")]
+use json as backend;
+
+/// Reminders can be at most this many minutes before or after a prayer time.
+pub const MAX_REMINDER_MINUTES: i32 = 120;
+/// Used when an older file has neither `reminders` nor `warning_minutes`
+const DEFAULT_REMINDER_MINUTES: i32 = 15;
+/// Locations kept to switch between from the tray, including the current one.
+const MAX_RECENT_LOCATIONS: usize = 5;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Location {
+    pub country: String,
+    pub city: String,
+    pub district: String,
+    pub district_id: String,
+}
+
+impl Location {
+    /// e.g. "KADIKÖY, İSTANBUL", or only the city when the district is the same
+    pub fn label(&self) -> String {
+        if self.city == self.district {
+            self.city.clone()
+        } else {
+            format!("{}, {}", self.district, self.city)
+        }
+    }
+}
+
+/// When the window is brought to the front on an alert.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RaiseMode {
+    Never,
+    Always,
+    /// After a while without input, so it doesn't steal focus while typing.
+    #[default]
+    WhenIdle,
+}
+
+impl From<u32> for RaiseMode {
+    fn from(value: u32) -> Self {
+        match value {
+            0 => RaiseMode::Never,
+            1 => RaiseMode::Always,
+            _ => RaiseMode::WhenIdle,
+        }
+    }
+}
+
+impl RaiseMode {
+    /// Index of the choice in the UI
+    pub fn index(self) -> u32 {
+        self as u32
+    }
+
+    /// Name in GSettings & preference files
+    pub fn nick(self) -> &'static str {
+        match self {
+            RaiseMode::Never => "never",
+            RaiseMode::Always => "always",
+            RaiseMode::WhenIdle => "when-idle",
+        }
+    }
+
+    pub fn from_nick(nick: &str) -> Self {
+        match nick {
+            "never" => RaiseMode::Never,
+            "always" => RaiseMode::Always,
+            _ => RaiseMode::WhenIdle,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Preferences {
+    pub country: RefCell<String>,
+    pub city: RefCell<String>,
+    pub district: RefCell<String>,
+    pub district_id: RefCell<String>,
+    /// The latest location first
+    #[serde(default)]
+    pub recent_locations: RefCell<Vec<Location>>,
+
+    /// Only read to migrate older files to `reminders`
+    #[serde(default, skip_serializing)]
+    pub warning_minutes: RefCell<Option<u8>>,
+
+    #[serde(default = "default_true")]
+    pub play_sound: RefCell<bool>,
+    /// Only read to migrate older files to `raise_mode`
+    #[serde(default, skip_serializing)]
+    pub raise_window: RefCell<Option<bool>>,
+    #[serde(default)]
+    pub raise_mode: RefCell<RaiseMode>,
+    /// Show a fullscreen countdown instead of raising the window.
+    #[serde(default)]
+    pub fullscreen_overlay: RefCell<bool>,
+    /// Start hidden in the tray after logging in.
+    #[serde(default)]
+    pub run_at_login: RefCell<bool>,
+    /// Pause the playing media players while the alert sound plays.
+    #[serde(default)]
+    pub pause_media: RefCell<bool>,
+
+    // Per prayer, indexed by `Prayer::index()`
+    #[serde(default = "default_all_prayers")]
+    pub notify_before: RefCell<Vec<bool>>,
+    #[serde(default = "default_all_prayers")]
+    pub notify_at_time: RefCell<Vec<bool>>,
+    /// Minutes before each prayer time to remind, negative ones remind after it.
+    #[serde(default)]
+    pub reminders: RefCell<Vec<Vec<i32>>>,
+    /// Sound file played at each prayer time, empty for the bundled sound.
+    #[serde(default)]
+    pub prayer_sounds: RefCell<Vec<String>>,
+    /// From 0.0 to 1.0
+    #[serde(default)]
+    pub volumes: RefCell<Vec<f64>>,
+    #[serde(default)]
+    pub fade_in: RefCell<Vec<bool>>,
+    /// Silent prayers only show the notification.
+    #[serde(default)]
+    pub silent: RefCell<Vec<bool>>,
+    /// Play the sound even in the desktop's Do-Not-Disturb mode.
+    #[serde(default)]
+    pub override_dnd: RefCell<Vec<bool>>,
+
+    /// Hours of the day without alert sounds, e.g. from 22 to 6.
+    #[serde(default)]
+    pub quiet_hours: RefCell<bool>,
+    #[serde(default = "default_quiet_hours_start")]
+    pub quiet_hours_start: RefCell<u32>,
+    #[serde(default = "default_quiet_hours_end")]
+    pub quiet_hours_end: RefCell<u32>,
+
+    #[serde(default = "default_window_width")]
+    pub window_width: RefCell<i32>,
+    #[serde(default = "default_window_height")]
+    pub window_height: RefCell<i32>,
+}
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PreferencesJson {
+    pub preferences: Preferences,
+}
+
+fn default_true() -> RefCell<bool> {
+    RefCell::new(true)
+}
+
+fn default_all_prayers() -> RefCell<Vec<bool>> {
+    RefCell::new(vec![true; Prayer::ALL.len()])
+}
+
+fn default_quiet_hours_start() -> RefCell<u32> {
+    RefCell::new(22)
+}
+
+fn default_quiet_hours_end() -> RefCell<u32> {
+    RefCell::new(6)
+}
+
+fn default_window_width() -> RefCell<i32> {
+    RefCell::new(-1)
+}
+
+fn default_window_height() -> RefCell<i32> {
+    RefCell::new(510)
+}
+
+/// Missing entries (e.g. a shorter list in an edited file) use `default`.
+fn per_prayer<T: Copy>(list: &RefCell<Vec<T>>, prayer: Prayer, default: T) -> T {
+    list.borrow()
+        .get(prayer.index())
+        .copied()
+        .unwrap_or(default)
+}
+
+fn set_per_prayer<T: Clone>(list: &RefCell<Vec<T>>, prayer: Prayer, value: T, default: T) {
+    let mut list = list.borrow_mut();
+    if list.len() < Prayer::ALL.len() {
+        list.resize(Prayer::ALL.len(), default);
+    }
+    list[prayer.index()] = value;
+}
+
+impl Preferences {
+    pub fn notify_before(&self, prayer: Prayer) -> bool {
+        per_prayer(&self.notify_before, prayer, true)
+    }
+
+    pub fn notify_at_time(&self, prayer: Prayer) -> bool {
+        per_prayer(&self.notify_at_time, prayer, true)
+    }
+
+    pub fn set_notify_before(&self, prayer: Prayer, value: bool) {
+        set_per_prayer(&self.notify_before, prayer, value, true);
+    }
+
+    pub fn set_notify_at_time(&self, prayer: Prayer, value: bool) {
+        set_per_prayer(&self.notify_at_time, prayer, value, true);
+    }
+
+    pub fn volume(&self, prayer: Prayer) -> f64 {
+        per_prayer(&self.volumes, prayer, 1.0)
+    }
+
+    pub fn set_volume(&self, prayer: Prayer, value: f64) {
+        set_per_prayer(&self.volumes, prayer, value, 1.0);
+    }
+
+    pub fn fade_in(&self, prayer: Prayer) -> bool {
+        per_prayer(&self.fade_in, prayer, false)
+    }
+
+    pub fn set_fade_in(&self, prayer: Prayer, value: bool) {
+        set_per_prayer(&self.fade_in, prayer, value, false);
+    }
+
+    pub fn silent(&self, prayer: Prayer) -> bool {
+        per_prayer(&self.silent, prayer, false)
+    }
+
+    pub fn set_silent(&self, prayer: Prayer, value: bool) {
+        set_per_prayer(&self.silent, prayer, value, false);
+    }
+
+    pub fn override_dnd(&self, prayer: Prayer) -> bool {
+        per_prayer(&self.override_dnd, prayer, false)
+    }
+
+    pub fn set_override_dnd(&self, prayer: Prayer, value: bool) {
+        set_per_prayer(&self.override_dnd, prayer, value, false);
+    }
+
+    pub fn location(&self) -> Location {
+        Location {
+            country: self.country.borrow().clone(),
+            city: self.city.borrow().clone(),
+            district: self.district.borrow().clone(),
+            district_id: self.district_id.borrow().clone(),
+        }
+    }
+
+    pub fn set_location(&self, location: Location) {
+        self.country.replace(location.country);
+        self.city.replace(location.city);
+        self.district.replace(location.district);
+        self.district_id.replace(location.district_id);
+    }
+
+    /// Moves the current location to the top of the recent ones.
+    pub fn remember_location(&self) {
+        let location = self.location();
+        let mut recent = self.recent_locations.borrow_mut();
+
+        recent.retain(|l| l.district_id != location.district_id);
+        recent.insert(0, location);
+        recent.truncate(MAX_RECENT_LOCATIONS);
+    }
+
+    /// Whether `time` is in the quiet hours, which may span midnight.
+    pub fn is_quiet_hour(&self, time: NaiveTime) -> bool {
+        if !*self.quiet_hours.borrow() {
+            return false;
+        }
+
+        let start = *self.quiet_hours_start.borrow();
+        let end = *self.quiet_hours_end.borrow();
+        let hour = time.hour();
+
+        if start <= end {
+            start <= hour && hour < end
+        } else {
+            hour >= start || hour < end
+        }
+    }
+
+    pub fn reminders(&self, prayer: Prayer) -> Vec<i32> {
+        self.reminders
+            .borrow()
+            .get(prayer.index())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Whether a reminder before `prayer` has already been due, `remaining_minutes` before it.
+    pub fn is_warning(&self, prayer: Prayer, remaining_minutes: u32) -> bool {
+        self.notify_before(prayer)
+            && self
+                .reminders(prayer)
+                .iter()
+                .any(|m| *m > 0 && remaining_minutes <= *m as u32)
+    }
+
+    /// Keeps the reminders sorted from the earliest to the latest.
+    pub fn set_reminders(&self, prayer: Prayer, mut minutes: Vec<i32>) {
+        minutes.sort_unstable_by(|a, b| b.cmp(a));
+        minutes.dedup();
+
+        let mut reminders = self.reminders.borrow_mut();
+        if reminders.len() < Prayer::ALL.len() {
+            reminders.resize(Prayer::ALL.len(), Vec::new());
+        }
+        reminders[prayer.index()] = minutes;
+    }
+
+    pub fn prayer_sound(&self, prayer: Prayer) -> Option<PathBuf> {
+        self.prayer_sounds
+            .borrow()
+            .get(prayer.index())
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+    }
+
+    pub fn set_prayer_sound(&self, prayer: Prayer, path: String) {
+        set_per_prayer(&self.prayer_sounds, prayer, path, String::new());
+    }
+
+    /// Turns the single `warning_minutes` of older versions into reminders for every
+    /// prayer, and their `raise_window` switch into a raise mode.
+    pub fn migrate(&self) {
+        if let Some(raise_window) = self.raise_window.take() {
+            self.raise_mode.replace(if raise_window {
+                RaiseMode::Always
+            } else {
+                RaiseMode::Never
+            });
+        }
+
+        let warning_minutes = self.warning_minutes.take();
+
+        if self.reminders.borrow().is_empty() {
+            let minutes = warning_minutes
+                .map(i32::from)
+                .unwrap_or(DEFAULT_REMINDER_MINUTES);
+
+            self.reminders
+                .replace(vec![vec![minutes]; Prayer::ALL.len()]);
+        }
+    }
+
+    /// Checks values that can't come from the UI, e.g. in edited or imported files.
+    pub fn validate(&self) -> Result<(), String> {
+        let reminders = self.reminders.borrow();
+        if let Some(minutes) = reminders
+            .iter()
+            .flatten()
+            .find(|m| m.abs() > MAX_REMINDER_MINUTES)
+        {
+            return Err(format!(
+                "reminders must be within {MAX_REMINDER_MINUTES} minutes, got {minutes}"
+            ));
+        }
+        // The alert at the prayer time already covers it
+        if reminders.iter().flatten().any(|m| *m == 0) {
+            return Err("reminders can't be 0 minutes from the prayer time".to_string());
+        }
+
+        if let Some(volume) = self
+            .volumes
+            .borrow()
+            .iter()
+            .find(|v| !(0.0..=1.0).contains(*v))
+        {
+            return Err(format!("volumes must be within 0 and 1, got {volume}"));
+        }
+
+        for hour in [&self.quiet_hours_start, &self.quiet_hours_end] {
+            let hour = *hour.borrow();
+            if hour > 23 {
+                return Err(format!("quiet hours must be within 0 and 23, got {hour}"));
+            }
+        }
+
+        if self.district_id.borrow().is_empty() {
+            return Err("district_id is empty".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+/// For the window, `main()` checks that the preferences can be loaded before.
+impl Default for PreferencesJson {
+    fn default() -> Self {
+        backend::load().expect("Couldn't load the preferences")
+    }
+}
+
+/// Whether the preferences can be read & saved, e.g. the GSettings schema is installed.
+pub fn check() -> Result<(), String> {
+    backend::check()
+}
+
+impl PreferencesJson {
+    /// Reads the stored preferences, and moves older ones to the current format.
+    pub fn load() -> Result<Self, String> {
+        backend::load()
+    }
+
+    /// Reads the stored preferences without writing anything, for commands that only
+    /// show them.
+    pub fn read() -> Result<Self, String> {
+        backend::read_only()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        backend::save(self)
+    }
+
+    /// Keeps the values changed in `self` since `base` was stored, and takes the
+    /// rest from `stored`, e.g. unsaved changes over the ones made outside the app.
+    pub fn merge(&self, base: &Self, stored: &Self) -> Self {
+        use serde_json::{to_value, Value};
+
+        let (Ok(Value::Object(ours)), Ok(Value::Object(base)), Ok(Value::Object(mut merged))) = (
+            to_value(&self.preferences),
+            to_value(&base.preferences),
+            to_value(&stored.preferences),
+        ) else {
+            return self.clone();
+        };
+
+        for (key, value) in ours {
+            if base.get(&key) != Some(&value) {
+                merged.insert(key, value);
+            }
+        }
+
+        serde_json::from_value(Value::Object(merged))
+            .map(|preferences| Self { preferences })
+            .unwrap_or_else(|_| self.clone())
+    }
+
+    /// Reads the stored preferences again, e.g. after they are changed outside of the app.
+    pub fn reload() -> Result<Self, String> {
+        backend::reload()
+    }
+
+    /// Calls `f` whenever the stored preferences change. The returned watcher must be kept alive.
+    pub fn watch(f: impl Fn() + 'static) -> Option<glib::Object> {
+        backend::watch(f)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// The bundled defaults, with a 15 minute reminder before every prayer.
+    pub fn defaults() -> PreferencesJson {
+        json::parse(include_str!("../../data/preferences.json")).unwrap()
+    }
+
+    #[test]
+    fn merge_keeps_unsaved_changes_and_outside_ones() {
+        let base = defaults();
+
+        let ours = base.clone();
+        ours.preferences.set_volume(Prayer::Fajr, 0.5);
+
+        let stored = base.clone();
+        stored.preferences.play_sound.replace(false);
+        stored.preferences.district_id.replace("9206".to_string());
+
+        let merged = ours.merge(&base, &stored);
+
+        assert_eq!(merged.preferences.volume(Prayer::Fajr), 0.5);
+        assert!(!*merged.preferences.play_sound.borrow());
+        assert_eq!(*merged.preferences.district_id.borrow(), "9206");
+    }
+
+    #[test]
+    fn merge_without_unsaved_changes_is_the_stored_preferences() {
+        let base = defaults();
+
+        let stored = base.clone();
+        stored.preferences.quiet_hours.replace(true);
+
+        assert_eq!(base.merge(&base, &stored), stored);
+    }
+
+    #[test]
+    fn validate_rejects_values_the_ui_cant_set() {
+        let pref = defaults();
+        pref.preferences.set_volume(Prayer::Asr, 1.5);
+        assert!(pref.preferences.validate().is_err());
+
+        let pref = defaults();
+        pref.preferences
+            .set_reminders(Prayer::Asr, vec![MAX_REMINDER_MINUTES + 1]);
+        assert!(pref.preferences.validate().is_err());
+
+        let pref = defaults();
+        pref.preferences.set_reminders(Prayer::Asr, vec![15, 0]);
+        assert!(pref.preferences.validate().is_err());
+
+        let pref = defaults();
+        pref.preferences.quiet_hours_end.replace(24);
+        assert!(pref.preferences.validate().is_err());
+
+        assert!(defaults().preferences.validate().is_ok());
+    }
+}
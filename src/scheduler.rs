@@ -0,0 +1,283 @@
+use chrono::{Local, NaiveDateTime, TimeDelta};
+
+use crate::cache::CacheJson;
+use crate::prayer::{self, Prayer};
+use crate::preferences::Preferences;
+
+/// Events missed by more than this, e.g. during a long suspend, are dropped.
+pub const MAX_LATENESS: TimeDelta = TimeDelta::minutes(10);
+
+/// Source of the wall-clock time, so the scheduler can run on a fake clock.
+pub trait Clock {
+    fn now(&self) -> NaiveDateTime;
+}
+
+#[derive(Debug)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> NaiveDateTime {
+        Local::now().naive_local()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EventKind {
+    /// Minutes before the prayer time, negative ones are after it.
+    Reminder(i32),
+    PrayerTime,
+}
+
+impl EventKind {
+    /// Minutes before the prayer time, `0` for the prayer time itself.
+    pub fn minutes(self) -> i32 {
+        match self {
+            EventKind::Reminder(minutes) => minutes,
+            EventKind::PrayerTime => 0,
+        }
+    }
+
+    pub fn from_minutes(minutes: i32) -> Self {
+        match minutes {
+            0 => EventKind::PrayerTime,
+            m => EventKind::Reminder(m),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Event {
+    pub prayer: Prayer,
+    pub kind: EventKind,
+    /// Prayer time the event belongs to, which also tells its day.
+    pub prayer_time: NaiveDateTime,
+    pub at: NaiveDateTime,
+}
+
+impl Event {
+    pub fn new(prayer: Prayer, kind: EventKind, prayer_time: NaiveDateTime) -> Self {
+        Self {
+            prayer,
+            kind,
+            prayer_time,
+            at: prayer_time - TimeDelta::minutes(kind.minutes().into()),
+        }
+    }
+}
+
+/// Enabled events in `from < at <= to`, sorted by time.
+///
+/// The days around the range are checked too, as reminders can cross midnight.
+pub fn events_between(
+    cache: &CacheJson,
+    pref: &Preferences,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> Vec<Event> {
+    let mut events = Vec::new();
+
+    let first_day = from.date() - TimeDelta::days(1);
+    let last_day = to.date() + TimeDelta::days(1);
+
+    for date in first_day.iter_days().take_while(|d| *d <= last_day) {
+        let Some(times) = prayer::get_prayers_of_date(cache, date) else {
+            continue;
+        };
+
+        for prayer in Prayer::ALL {
+            let Some(prayer_time) = times.datetime_of(date, prayer) else {
+                continue;
+            };
+
+            if pref.notify_at_time(prayer) {
+                events.push(Event::new(prayer, EventKind::PrayerTime, prayer_time));
+            }
+
+            if pref.notify_before(prayer) {
+                for minutes in pref.reminders(prayer) {
+                    events.push(Event::new(
+                        prayer,
+                        EventKind::Reminder(minutes),
+                        prayer_time,
+                    ));
+                }
+            }
+        }
+    }
+
+    events.retain(|e| from < e.at && e.at <= to);
+    events.sort_by_key(|e| e.at);
+
+    events
+}
+
+/// Finds the events that became due since the last check, however long ago it was.
+#[derive(Debug)]
+pub struct Scheduler<C: Clock = SystemClock> {
+    clock: C,
+    last_check: Option<NaiveDateTime>,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new(SystemClock)
+    }
+}
+
+impl<C: Clock> Scheduler<C> {
+    pub fn new(clock: C) -> Self {
+        Self {
+            clock,
+            last_check: None,
+        }
+    }
+
+    /// Events due since the last call. The first call only starts counting.
+    pub fn due_events(&mut self, cache: &CacheJson, pref: &Preferences) -> Vec<Event> {
+        let now = self.clock.now();
+
+        let Some(last_check) = self.last_check.replace(now) else {
+            return Vec::new();
+        };
+
+        // The clock was set back, nothing became due in between
+        if now <= last_check {
+            return Vec::new();
+        }
+
+        let mut events = events_between(cache, pref, last_check, now);
+
+        let cutoff = now - MAX_LATENESS;
+        let missed = events.iter().filter(|e| e.at < cutoff).count();
+        if missed > 0 {
+            eprintln!("Dropping {missed} missed notification(s)");
+            events.retain(|e| e.at >= cutoff);
+        }
+
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use chrono::{NaiveDate, NaiveTime};
+
+    use super::*;
+    use crate::cache::tests::cache_with_times;
+    use crate::preferences::tests::defaults;
+
+    /// Shared with the scheduler, so a test can move it like a suspend or `date -s`.
+    #[derive(Clone)]
+    struct FakeClock(Rc<Cell<NaiveDateTime>>);
+
+    impl FakeClock {
+        fn set(&self, time: &str) {
+            self.0.set(at(time));
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> NaiveDateTime {
+            self.0.get()
+        }
+    }
+
+    fn at(time: &str) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 19)
+            .unwrap()
+            .and_time(NaiveTime::parse_from_str(time, "%H:%M").unwrap())
+    }
+
+    fn scheduler(time: &str) -> (Scheduler<FakeClock>, FakeClock) {
+        let clock = FakeClock(Rc::new(Cell::new(at(time))));
+
+        (Scheduler::new(clock.clone()), clock)
+    }
+
+    fn cache() -> CacheJson {
+        cache_with_times(
+            NaiveDate::from_ymd_opt(2026, 10, 18).unwrap(),
+            NaiveDate::from_ymd_opt(2026, 10, 20).unwrap(),
+        )
+    }
+
+    fn summary(events: &[Event]) -> Vec<(Prayer, EventKind, NaiveDateTime)> {
+        events.iter().map(|e| (e.prayer, e.kind, e.at)).collect()
+    }
+
+    #[test]
+    fn first_check_only_starts_counting() {
+        let (mut scheduler, _) = scheduler("11:50");
+
+        assert!(scheduler
+            .due_events(&cache(), &defaults().preferences)
+            .is_empty());
+    }
+
+    #[test]
+    fn catches_up_from_the_last_check() {
+        let pref = defaults().preferences;
+        pref.set_reminders(Prayer::Dhuhr, vec![10, 5]);
+        let (mut scheduler, clock) = scheduler("11:48");
+        scheduler.due_events(&cache(), &pref);
+
+        clock.set("12:00");
+
+        assert_eq!(
+            summary(&scheduler.due_events(&cache(), &pref)),
+            [
+                (Prayer::Dhuhr, EventKind::Reminder(10), at("11:50")),
+                (Prayer::Dhuhr, EventKind::Reminder(5), at("11:55")),
+                (Prayer::Dhuhr, EventKind::PrayerTime, at("12:00")),
+            ]
+        );
+
+        // Nothing is due twice
+        clock.set("12:01");
+        assert!(scheduler.due_events(&cache(), &pref).is_empty());
+    }
+
+    #[test]
+    fn drops_events_older_than_max_lateness() {
+        let pref = defaults().preferences;
+        let (mut scheduler, clock) = scheduler("10:00");
+        scheduler.due_events(&cache(), &pref);
+
+        // Woken from suspend 20 minutes after the reminder & 5 after the prayer time
+        clock.set("12:05");
+
+        assert_eq!(
+            summary(&scheduler.due_events(&cache(), &pref)),
+            [(Prayer::Dhuhr, EventKind::PrayerTime, at("12:00"))]
+        );
+        assert!(at("12:05") - at("11:45") > MAX_LATENESS);
+    }
+
+    #[test]
+    fn ignores_a_clock_set_back() {
+        let pref = defaults().preferences;
+        let (mut scheduler, clock) = scheduler("12:10");
+        scheduler.due_events(&cache(), &pref);
+
+        clock.set("11:00");
+        assert!(scheduler.due_events(&cache(), &pref).is_empty());
+
+        // Counts on from the new time
+        clock.set("11:46");
+        assert_eq!(
+            summary(&scheduler.due_events(&cache(), &pref)),
+            [(Prayer::Dhuhr, EventKind::Reminder(15), at("11:45"))]
+        );
+    }
+
+    #[test]
+    fn disabled_events_are_skipped() {
+        let pref = defaults().preferences;
+        pref.set_notify_before(Prayer::Dhuhr, false);
+        pref.set_notify_at_time(Prayer::Dhuhr, false);
+
+        assert!(events_between(&cache(), &pref, at("11:00"), at("13:00")).is_empty());
+    }
+}
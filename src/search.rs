@@ -0,0 +1,216 @@
+use chrono::NaiveDateTime;
+use formatx::formatx;
+use gettextrs::gettext;
+
+use crate::dbusservice::PrayerSource;
+use crate::prayer::Prayer;
+
+/// Result of the next prayer's countdown, the others are the prayers' keys.
+pub const NEXT_ID: &str = "next";
+
+/// Shorter searches would match nearly every keyword.
+const MIN_SEARCH_LENGTH: usize = 2;
+
+/// Words matching every result
+fn general_keywords() -> String {
+    gettext("prayer;prayers;prayer times;salah;namaz;vakit;adhan;ezan")
+}
+
+fn next_keywords() -> String {
+    gettext("next;countdown;remaining;time left")
+}
+
+fn prayer_keywords(prayer: Prayer) -> String {
+    match prayer {
+        Prayer::Fajr | Prayer::FajrNextDay => gettext("fajr;imsak;suhoor;sahur;dawn"),
+        Prayer::Sunrise => gettext("sunrise;güneş"),
+        Prayer::Dhuhr => gettext("dhuhr;zuhr;noon;öğle"),
+        Prayer::Asr => gettext("asr;afternoon;ikindi"),
+        Prayer::Maghrib => gettext("maghrib;iftar;sunset;akşam"),
+        Prayer::Isha => gettext("isha;night;yatsı"),
+    }
+}
+
+/// Whether `term` starts one of the ";" separated `keywords`, or one of their words.
+fn matches(keywords: &str, term: &str) -> bool {
+    keywords
+        .split(';')
+        .flat_map(|k| std::iter::once(k).chain(k.split_whitespace()))
+        .any(|k| k.trim().to_lowercase().starts_with(term))
+}
+
+/// Results whose keywords, or the general ones, are matched by all of the terms.
+pub fn result_ids(terms: &[String]) -> Vec<String> {
+    let terms: Vec<String> = terms
+        .iter()
+        .map(|t| t.trim().to_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect();
+
+    if terms.concat().chars().count() < MIN_SEARCH_LENGTH {
+        return Vec::new();
+    }
+
+    let general = general_keywords();
+    let candidates = std::iter::once((NEXT_ID, next_keywords())).chain(
+        Prayer::ALL
+            .iter()
+            .map(|p| (p.key(), format!("{};{}", p.name(), prayer_keywords(*p)))),
+    );
+
+    candidates
+        .filter(|(_, keywords)| {
+            terms
+                .iter()
+                .all(|t| matches(&general, t) || matches(keywords, t))
+        })
+        .map(|(id, _)| id.to_string())
+        .collect()
+}
+
+/// Narrows the results of an earlier search as more is typed.
+pub fn subsearch_ids(previous: &[String], terms: &[String]) -> Vec<String> {
+    result_ids(terms)
+        .into_iter()
+        .filter(|id| previous.contains(id))
+        .collect()
+}
+
+/// What the overview shows for a result.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResultMeta {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+}
+
+/// e.g. "Asr in 01:23" & "At 15:42 in KADIKÖY, İSTANBUL" for the next prayer,
+/// "Asr 15:42" & "Today in KADIKÖY, İSTANBUL" for the others.
+pub fn result_meta(id: &str, source: &impl PrayerSource, now: NaiveDateTime) -> Option<ResultMeta> {
+    let location = source.location().label();
+
+    let (name, description) = if id == NEXT_ID {
+        let (prayer, time) = source.next_prayer(now)?;
+        let minutes = ((time - now).num_seconds() + 59) / 60;
+
+        (
+            formatx!(
+                gettext("{prayer} in {remaining}"),
+                prayer = prayer.name(),
+                remaining = format!("{:0>2}:{:0>2}", minutes / 60, minutes % 60)
+            )
+            .unwrap(),
+            formatx!(
+                gettext("At {time} in {location}"),
+                time = time.format("%H:%M"),
+                location = &location
+            )
+            .unwrap(),
+        )
+    } else {
+        let prayer = *Prayer::ALL.iter().find(|p| p.key() == id)?;
+        let times = source.times_of(now.date())?;
+
+        (
+            format!("{} {}", prayer.name(), times.time_of(prayer)),
+            formatx!(gettext("Today in {location}"), location = &location).unwrap(),
+        )
+    };
+
+    Some(ResultMeta {
+        id: id.to_string(),
+        name,
+        description,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Local, NaiveDate, NaiveTime};
+
+    use super::*;
+    use crate::dbusservice::tests::FakeSource;
+
+    fn ids(terms: &[&str]) -> Vec<String> {
+        result_ids(&terms.iter().map(|t| t.to_string()).collect::<Vec<_>>())
+    }
+
+    fn today_at(time: &str) -> NaiveDateTime {
+        Local::now()
+            .date_naive()
+            .and_time(NaiveTime::parse_from_str(time, "%H:%M").unwrap())
+    }
+
+    #[test]
+    fn short_searches_match_nothing() {
+        assert!(ids(&[]).is_empty());
+        assert!(ids(&["a"]).is_empty());
+        assert!(ids(&[" ", "i "]).is_empty());
+    }
+
+    #[test]
+    fn prayers_match_their_names_and_keywords() {
+        assert_eq!(ids(&["asr"]), ["asr"]);
+        assert_eq!(ids(&["IFTAR"]), ["maghrib"]);
+        assert_eq!(ids(&["güneş"]), ["sunrise"]);
+        // Start of a word of a keyword
+        assert_eq!(ids(&["left"]), ["next"]);
+    }
+
+    #[test]
+    fn general_keywords_match_every_result() {
+        let all: Vec<_> = std::iter::once(NEXT_ID)
+            .chain(Prayer::ALL.iter().map(|p| p.key()))
+            .collect();
+
+        assert_eq!(ids(&["namaz"]), all);
+        // Every term has to match
+        assert_eq!(ids(&["namaz", "isha"]), ["isha"]);
+        assert!(ids(&["isha", "dawn"]).is_empty());
+    }
+
+    #[test]
+    fn subsearch_narrows_the_previous_results() {
+        let previous = ids(&["prayer"]);
+
+        assert_eq!(
+            subsearch_ids(&previous, &["prayer".into(), "ma".into()]),
+            ["maghrib"]
+        );
+        assert!(subsearch_ids(&["asr".into()], &["isha".into()]).is_empty());
+    }
+
+    #[test]
+    fn next_result_counts_down() {
+        let meta = result_meta(NEXT_ID, &FakeSource::new("9541"), today_at("10:45")).unwrap();
+
+        assert_eq!(
+            meta,
+            ResultMeta {
+                id: NEXT_ID.to_string(),
+                name: "Dhuhr in 01:15".to_string(),
+                description: "At 12:00 in KADIKÖY, İSTANBUL".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn prayer_results_show_todays_time() {
+        let meta = result_meta("asr", &FakeSource::new("9541"), today_at("10:45")).unwrap();
+
+        assert_eq!(meta.name, "Asr 15:30");
+        assert_eq!(meta.description, "Today in KADIKÖY, İSTANBUL");
+    }
+
+    #[test]
+    fn unknown_ids_and_days_have_no_meta() {
+        let source = FakeSource::new("9541");
+
+        assert_eq!(result_meta("tahajjud", &source, today_at("10:45")), None);
+
+        let uncached = NaiveDate::from_ymd_opt(2000, 1, 1)
+            .unwrap()
+            .and_hms_opt(10, 45, 0);
+        assert_eq!(result_meta("asr", &source, uncached.unwrap()), None);
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+
+use chrono::Local;
+use gtk::gio;
+use gtk::glib::{self, prelude::*};
+
+use crate::dbusservice::PrayerSource;
+use crate::search;
+use crate::APP_ID;
+
+/// Also in `data/<APP_ID>.search-provider.ini`
+pub const OBJECT_PATH: &str = "/io/github/eminfedar/VaktiSalah/SearchProvider";
+const INTERFACE: &str = "org.gnome.Shell.SearchProvider2";
+
+const INTROSPECTION_XML: &str = r#"
+<node>
+  <interface name="org.gnome.Shell.SearchProvider2">
+    <method name="GetInitialResultSet">
+      <arg name="terms" type="as" direction="in"/>
+      <arg name="results" type="as" direction="out"/>
+    </method>
+    <method name="GetSubsearchResultSet">
+      <arg name="previous_results" type="as" direction="in"/>
+      <arg name="terms" type="as" direction="in"/>
+      <arg name="results" type="as" direction="out"/>
+    </method>
+    <method name="GetResultMetas">
+      <arg name="identifiers" type="as" direction="in"/>
+      <arg name="metas" type="aa{sv}" direction="out"/>
+    </method>
+    <method name="ActivateResult">
+      <arg name="identifier" type="s" direction="in"/>
+      <arg name="terms" type="as" direction="in"/>
+      <arg name="timestamp" type="u" direction="in"/>
+    </method>
+    <method name="LaunchSearch">
+      <arg name="terms" type="as" direction="in"/>
+      <arg name="timestamp" type="u" direction="in"/>
+    </method>
+  </interface>
+</node>
+"#;
+
+fn invalid_args() -> glib::Error {
+    glib::Error::new(gio::DBusError::InvalidArgs, "Unexpected arguments")
+}
+
+fn result_metas(identifiers: &[String], source: &impl PrayerSource) -> glib::Variant {
+    let now = Local::now().naive_local();
+
+    let metas: Vec<HashMap<&str, glib::Variant>> = identifiers
+        .iter()
+        .filter_map(|id| search::result_meta(id, source, now))
+        .map(|meta| {
+            HashMap::from([
+                ("id", meta.id.to_variant()),
+                ("name", meta.name.to_variant()),
+                ("description", meta.description.to_variant()),
+                ("gicon", APP_ID.to_variant()),
+            ])
+        })
+        .collect();
+
+    (metas,).to_variant()
+}
+
+fn call<S: PrayerSource>(
+    source: Option<S>,
+    activate: &dyn Fn(),
+    method: &str,
+    params: &glib::Variant,
+) -> Result<Option<glib::Variant>, glib::Error> {
+    match method {
+        // Without prayer times there is nothing to show
+        "GetInitialResultSet" => {
+            let (terms,) = params.get::<(Vec<String>,)>().ok_or_else(invalid_args)?;
+            let ids = match source {
+                Some(_) => search::result_ids(&terms),
+                None => Vec::new(),
+            };
+
+            Ok(Some((ids,).to_variant()))
+        }
+        "GetSubsearchResultSet" => {
+            let (previous, terms) = params
+                .get::<(Vec<String>, Vec<String>)>()
+                .ok_or_else(invalid_args)?;
+            let ids = match source {
+                Some(_) => search::subsearch_ids(&previous, &terms),
+                None => Vec::new(),
+            };
+
+            Ok(Some((ids,).to_variant()))
+        }
+        "GetResultMetas" => {
+            let (identifiers,) = params.get::<(Vec<String>,)>().ok_or_else(invalid_args)?;
+
+            match source {
+                Some(source) => Ok(Some(result_metas(&identifiers, &source))),
+                None => Ok(Some(
+                    (Vec::<HashMap<String, glib::Variant>>::new(),).to_variant(),
+                )),
+            }
+        }
+        "ActivateResult" | "LaunchSearch" => {
+            activate();
+            Ok(None)
+        }
+        _ => Err(glib::Error::new(
+            gio::DBusError::UnknownMethod,
+            &format!("No such method: {method}"),
+        )),
+    }
+}
+
+/// Answers GNOME Shell's searches on `connection`, which must own the app's bus name.
+/// `activate` opens the window when a result is chosen.
+pub fn register<S: PrayerSource + 'static>(
+    connection: &gio::DBusConnection,
+    source: impl Fn() -> Option<S> + 'static,
+    activate: impl Fn() + 'static,
+) -> Result<gio::RegistrationId, glib::Error> {
+    let node = gio::DBusNodeInfo::for_xml(INTROSPECTION_XML)?;
+    let interface = node.lookup_interface(INTERFACE).unwrap();
+
+    connection
+        .register_object(OBJECT_PATH, &interface)
+        .method_call(move |_, _, _, _, method, params, invocation| {
+            invocation.return_result(call(source(), &activate, method, &params));
+        })
+        .build()
+}
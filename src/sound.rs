@@ -1,16 +1,447 @@
-use rodio::{source::Source, Decoder, OutputStream};
-use std::{io::Cursor, time::Duration};
+use rodio::{
+    cpal::{self, traits::HostTrait},
+    source::{self, SineWave, Source},
+    Decoder, DeviceTrait, OutputStream, OutputStreamHandle, Sink,
+};
+use std::{
+    fs::File,
+    io::{BufReader, Cursor},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        LazyLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::mpris::MediaPauser;
 
 static ALERT_SOUND: &[u8] = include_bytes!("../data/alert.ogg");
 
-pub fn play_alert() {
-    let (_stream, stream_handle) = OutputStream::try_default().unwrap();
+/// File extensions of the sound files that can be chosen, depending on enabled features.
+pub const SUPPORTED_EXTENSIONS: &[&str] = &[
+    "ogg",
+    "oga",
+    #[cfg(feature = "flac")]
+    "flac",
+    #[cfg(feature = "mp3")]
+    "mp3",
+    #[cfg(feature = "wav")]
+    "wav",
+];
+
+/// Sounds with fade-in reach their volume after this long.
+const FADE_IN_DURATION: Duration = Duration::from_secs(10);
+/// How often the end of the playing sound is checked.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// How often a change of the default audio device is checked while idle.
+const DEVICE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+type BoxedSource = Box<dyn Source<Item = f32> + Send>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sound {
+    /// The bundled `alert.ogg`
+    Alert,
+    /// A rising three note chime, distinct from the alert.
+    PrayerTime,
+    /// A user chosen sound file, e.g. a full adhan. Falls back to the alert.
+    File(PathBuf),
+}
+
+impl Sound {
+    /// The custom sound file if there is one, `default` otherwise.
+    pub fn custom_or(path: Option<PathBuf>, default: Sound) -> Self {
+        path.map(Sound::File).unwrap_or(default)
+    }
+
+    fn alert_source() -> BoxedSource {
+        // The bundled file is known to decode
+        let source = Decoder::new_vorbis(Cursor::new(ALERT_SOUND)).unwrap();
+
+        Box::new(source.convert_samples())
+    }
+
+    fn source(&self) -> BoxedSource {
+        match self {
+            Sound::Alert => Self::alert_source(),
+            Sound::PrayerTime => {
+                let notes = [523.25, 659.25, 783.99].map(|frequency| {
+                    SineWave::new(frequency)
+                        .take_duration(Duration::from_millis(450))
+                        .fade_in(Duration::from_millis(30))
+                        .amplify(0.25)
+                });
+
+                Box::new(source::from_iter(notes))
+            }
+            Sound::File(path) => {
+                let decoded = File::open(path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|f| Decoder::new(BufReader::new(f)).map_err(|e| e.to_string()));
+
+                match decoded {
+                    Ok(source) => Box::new(source.convert_samples()),
+                    Err(e) => {
+                        eprintln!("Couldn't play {}: {e}", path.display());
+                        Self::alert_source()
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Command {
+    Play {
+        sound: Sound,
+        volume: f32,
+        fade_in: Duration,
+        pause_media: bool,
+    },
+    Stop,
+    SetVolume(f32),
+}
+
+/// Where the audio service plays. Tests can hand out `Sink::new_idle()` sinks
+/// to run without an audio device.
+pub trait Output {
+    /// A sink to play on, `None` to stay silent.
+    fn sink(&mut self) -> Option<Sink>;
+
+    /// Called regularly while idle, to follow changes of the audio device.
+    fn refresh(&mut self) {}
+}
+
+/// The default audio device, reopened when the default changes.
+#[derive(Default)]
+pub struct DeviceOutput {
+    stream: Option<(OutputStream, OutputStreamHandle)>,
+    device_name: Option<String>,
+}
+
+fn default_device_name() -> Option<String> {
+    cpal::default_host()
+        .default_output_device()
+        .and_then(|d| d.name().ok())
+}
+
+impl DeviceOutput {
+    fn connect(&mut self) {
+        self.stream = None;
+
+        let device = cpal::default_host().default_output_device();
+        self.device_name = device.as_ref().and_then(|d| d.name().ok());
+
+        let Some(device) = device else {
+            eprintln!("No audio device found, alerts will be silent.");
+            return;
+        };
+
+        match OutputStream::try_from_device(&device) {
+            Ok(stream) => self.stream = Some(stream),
+            Err(e) => eprintln!("Couldn't open the audio device: {e}"),
+        }
+    }
+}
+
+impl Output for DeviceOutput {
+    fn sink(&mut self) -> Option<Sink> {
+        if self.stream.is_none() {
+            self.connect();
+        }
+
+        let (_, handle) = self.stream.as_ref()?;
+
+        match Sink::try_new(handle) {
+            Ok(sink) => Some(sink),
+            Err(e) => {
+                eprintln!("Couldn't play on the audio device: {e}");
+                // Try the device again next time
+                self.stream = None;
+                None
+            }
+        }
+    }
+
+    fn refresh(&mut self) {
+        if default_device_name() != self.device_name {
+            eprintln!("Default audio device changed");
+            self.connect();
+        }
+    }
+}
+
+/// Whether the service is playing, readable without a round trip.
+static PLAYING: AtomicBool = AtomicBool::new(false);
+
+/// Owns the output on its own thread and plays one sound at a time.
+///
+/// Media players paused for a sound are resumed once nothing plays anymore.
+pub struct AudioService<O: Output> {
+    output: O,
+    sink: Option<Sink>,
+    media: MediaPauser,
+}
+
+impl<O: Output> AudioService<O> {
+    pub fn new(output: O, media: MediaPauser) -> Self {
+        Self {
+            output,
+            sink: None,
+            media,
+        }
+    }
+
+    fn run(mut self, commands: Receiver<Command>) {
+        let mut last_refresh = Instant::now();
+
+        loop {
+            let timeout = match self.sink {
+                Some(_) => POLL_INTERVAL,
+                None => DEVICE_CHECK_INTERVAL,
+            };
+
+            match commands.recv_timeout(timeout) {
+                Ok(command) => self.handle(command),
+                Err(RecvTimeoutError::Timeout) => (),
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+
+            if self.sink.as_ref().is_some_and(Sink::empty) {
+                self.sink = None;
+            }
+            PLAYING.store(self.sink.is_some(), Ordering::Relaxed);
+
+            if self.sink.is_none() {
+                self.media.resume();
+            }
+
+            // Switching devices would cut the playing sound
+            if self.sink.is_none() && last_refresh.elapsed() >= DEVICE_CHECK_INTERVAL {
+                self.output.refresh();
+                last_refresh = Instant::now();
+            }
+        }
+    }
+
+    fn handle(&mut self, command: Command) {
+        match command {
+            Command::Play {
+                sound,
+                volume,
+                fade_in,
+                pause_media,
+            } => {
+                // A new sound replaces the one playing
+                self.stop();
+
+                let Some(sink) = self.output.sink() else {
+                    return;
+                };
+
+                if pause_media {
+                    self.media.pause_playing();
+                }
+
+                sink.set_volume(volume);
+                if fade_in.is_zero() {
+                    sink.append(sound.source());
+                } else {
+                    sink.append(sound.source().fade_in(fade_in));
+                }
+
+                self.sink = Some(sink);
+            }
+            Command::Stop => self.stop(),
+            Command::SetVolume(volume) => {
+                if let Some(sink) = &self.sink {
+                    sink.set_volume(volume);
+                }
+            }
+        }
+    }
+
+    fn stop(&mut self) {
+        if let Some(sink) = self.sink.take() {
+            sink.stop();
+        }
+    }
+}
+
+static SERVICE: LazyLock<Sender<Command>> = LazyLock::new(|| {
+    let (sender, receiver) = mpsc::channel();
+
+    let spawned = thread::Builder::new().name("audio".into()).spawn(move || {
+        AudioService::new(DeviceOutput::default(), MediaPauser::session()).run(receiver)
+    });
+
+    if let Err(e) = spawned {
+        eprintln!("Couldn't start the audio service: {e}");
+    }
+
+    sender
+});
+
+fn send(command: Command) {
+    if SERVICE.send(command).is_err() {
+        eprintln!("Audio service is not running");
+    }
+}
+
+/// Plays `sound` at `volume` (0.0 to 1.0), replacing the one playing.
+///
+/// With `pause_media`, the playing media players are paused until it ends.
+pub fn play(sound: Sound, volume: f64, fade_in: bool, pause_media: bool) {
+    send(Command::Play {
+        sound,
+        volume: volume as f32,
+        fade_in: if fade_in {
+            FADE_IN_DURATION
+        } else {
+            Duration::ZERO
+        },
+        pause_media,
+    });
+}
+
+/// Changes the volume of the playing sound, e.g. while a volume slider is dragged.
+pub fn set_volume(volume: f64) {
+    send(Command::SetVolume(volume as f32));
+}
+
+pub fn stop() {
+    send(Command::Stop);
+}
+
+pub fn is_playing() -> bool {
+    PLAYING.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use rodio::queue::SourcesQueueOutput;
+
+    use super::*;
+
+    /// Hands out idle sinks, keeping their outputs to pull the samples from.
+    #[derive(Default)]
+    struct FakeOutput {
+        no_device: bool,
+        outputs: Vec<SourcesQueueOutput<f32>>,
+    }
+
+    impl Output for FakeOutput {
+        fn sink(&mut self) -> Option<Sink> {
+            if self.no_device {
+                return None;
+            }
+
+            let (sink, output) = Sink::new_idle();
+            self.outputs.push(output);
+
+            Some(sink)
+        }
+    }
+
+    fn service(output: FakeOutput) -> AudioService<FakeOutput> {
+        AudioService::new(output, MediaPauser::new(None))
+    }
+
+    fn play(sound: Sound, volume: f32) -> Command {
+        Command::Play {
+            sound,
+            volume,
+            fade_in: Duration::ZERO,
+            pause_media: true,
+        }
+    }
+
+    fn samples(source: BoxedSource) -> Vec<f32> {
+        source.take(20_000).collect()
+    }
+
+    #[test]
+    fn plays_at_the_volume() {
+        let mut service = service(FakeOutput::default());
+
+        service.handle(play(Sound::Alert, 0.4));
+
+        let sink = service.sink.as_ref().unwrap();
+        assert_eq!(sink.volume(), 0.4);
+        assert_eq!(sink.len(), 1);
+        assert!(!sink.is_paused());
+        assert!(service.output.outputs[0]
+            .by_ref()
+            .take(48_000)
+            .any(|s| s != 0.0));
+    }
+
+    #[test]
+    fn new_sound_replaces_the_playing_one() {
+        let mut service = service(FakeOutput::default());
+
+        service.handle(play(Sound::Alert, 1.0));
+        service.handle(play(Sound::PrayerTime, 1.0));
+
+        assert_eq!(service.output.outputs.len(), 2);
+        assert_eq!(service.sink.as_ref().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn stop_ends_the_sound() {
+        let mut service = service(FakeOutput::default());
+
+        service.handle(play(Sound::Alert, 1.0));
+        service.handle(Command::Stop);
+
+        assert!(service.sink.is_none());
+    }
+
+    #[test]
+    fn volume_changes_the_playing_sound() {
+        let mut service = service(FakeOutput::default());
+
+        // Nothing playing
+        service.handle(Command::SetVolume(0.2));
+        assert!(service.sink.is_none());
+
+        service.handle(play(Sound::Alert, 1.0));
+        service.handle(Command::SetVolume(0.2));
+
+        assert_eq!(service.sink.as_ref().unwrap().volume(), 0.2);
+    }
+
+    #[test]
+    fn silent_without_a_device() {
+        let mut service = service(FakeOutput {
+            no_device: true,
+            ..Default::default()
+        });
+
+        service.handle(play(Sound::Alert, 1.0));
+
+        assert!(service.sink.is_none());
+    }
+
+    #[test]
+    fn missing_file_plays_the_alert() {
+        let missing = Sound::File(PathBuf::from("/nonexistent/adhan.ogg"));
 
-    let file = Cursor::new(ALERT_SOUND);
+        assert_eq!(samples(missing.source()), samples(Sound::alert_source()));
+    }
 
-    let source = Decoder::new_vorbis(file).unwrap();
+    #[test]
+    fn undecodable_file_plays_the_alert() {
+        let path = std::env::temp_dir().join(format!("vaktisalah-{}.ogg", std::process::id()));
+        std::fs::write(&path, b"not a sound").unwrap();
 
-    stream_handle.play_raw(source.convert_samples()).unwrap();
+        let source = Sound::File(path.clone()).source();
+        std::fs::remove_file(&path).unwrap();
 
-    std::thread::sleep(Duration::from_secs(2));
+        assert_eq!(samples(source), samples(Sound::alert_source()));
+    }
 }
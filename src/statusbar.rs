@@ -0,0 +1,250 @@
+use std::io::{self, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::{Local, NaiveDateTime};
+use gettextrs::gettext;
+use gtk::glib;
+use serde_json::json;
+
+use crate::cache::CacheJson;
+use crate::cli::{self, Output};
+use crate::prayer::{self, Prayer, RemainingTime};
+use crate::preferences::{Preferences, PreferencesJson};
+
+/// The cache & preferences are read again this often while watching.
+const RELOAD_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The next prayer, as a status bar shows it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Status {
+    pub prayer: Prayer,
+    pub time: NaiveDateTime,
+    pub remaining: RemainingTime,
+    /// Rounded up, like the tray icon
+    pub remaining_minutes: u32,
+    /// Part of the time since the previous prayer that has passed, from 0 to 100
+    pub percentage: u8,
+    /// Whether a reminder of the next prayer has already been due
+    pub warning: bool,
+    /// Today's prayer times
+    pub tooltip: String,
+}
+
+impl Status {
+    /// "normal", or "warning" inside the reminders before the next prayer
+    pub fn class(&self) -> &'static str {
+        if self.warning {
+            "warning"
+        } else {
+            "normal"
+        }
+    }
+
+    /// e.g. "Asr 01:23", with seconds inside the warning window
+    pub fn text(&self) -> String {
+        let r = &self.remaining;
+        let remaining = if self.warning {
+            format!("{:0>2}:{:0>2}:{:0>2}", r.hours, r.minutes, r.seconds)
+        } else {
+            format!("{:0>2}:{:0>2}", r.hours, r.minutes)
+        };
+
+        format!("{} {remaining}", self.prayer.name())
+    }
+
+    /// Until the shown countdown changes: a second inside the warning window, a minute otherwise.
+    pub fn update_interval(&self) -> Duration {
+        if self.warning {
+            Duration::from_secs(1)
+        } else {
+            Duration::from_secs(u64::from(self.remaining.seconds) + 1)
+        }
+    }
+}
+
+/// The next prayer after `now`, `None` when the cache doesn't cover today & tomorrow.
+pub fn status(cache: &CacheJson, pref: &Preferences, now: NaiveDateTime) -> Option<Status> {
+    let todays_prayers = prayer::get_prayers_of_date(cache, now.date());
+    let tomorrows_prayers = prayer::get_prayers_of_date(cache, now.date().succ_opt()?);
+    let remaining =
+        prayer::calculate_remaining_time(&todays_prayers, &tomorrows_prayers, now.time())?;
+
+    let (previous, next) = prayer::surrounding_prayer_times(cache, now)?;
+    let progress = (now - previous).num_seconds() as f64 / (next - previous).num_seconds() as f64;
+
+    let r = &remaining;
+    let remaining_seconds =
+        u32::from(r.hours) * 3600 + u32::from(r.minutes) * 60 + u32::from(r.seconds);
+    let remaining_minutes = remaining_seconds.div_ceil(60);
+    // Tomorrow's Fajr after Isha is the same prayer for scripts
+    let prayer = Prayer::ALL[r.next_prayer.index()];
+
+    Some(Status {
+        prayer,
+        time: next,
+        remaining,
+        remaining_minutes,
+        percentage: (progress.clamp(0.0, 1.0) * 100.0).round() as u8,
+        warning: pref.is_warning(prayer, remaining_minutes),
+        tooltip: cli::human_day(now.date(), todays_prayers.as_ref()?),
+    })
+}
+
+/// A line for the status bar. Without a status, Waybar gets an empty module
+/// and the others an empty line, so a watching bar keeps running.
+pub fn render(status: Option<&Status>, output: &Output) -> Result<String, String> {
+    let Some(status) = status else {
+        return match output {
+            Output::Json => Ok(json!({
+                "text": "",
+                "tooltip": gettext("No prayer times are cached, open the app to update them"),
+                "class": "unavailable",
+            })
+            .to_string()),
+            _ => Ok(String::new()),
+        };
+    };
+
+    match output {
+        Output::Human => Ok(status.text()),
+        // Waybar's custom module format, with the prayer in "alt" for its format-icons
+        Output::Json => Ok(json!({
+            "text": status.text(),
+            "alt": status.prayer.key(),
+            "tooltip": status.tooltip,
+            "class": status.class(),
+            "percentage": status.percentage,
+        })
+        .to_string()),
+        Output::Format(template) => {
+            let r = &status.remaining;
+            let fields = vec![
+                ("prayer", status.prayer.name()),
+                ("key", status.prayer.key().to_string()),
+                ("time", status.time.format("%H:%M").to_string()),
+                (
+                    "remaining",
+                    format!("{:0>2}:{:0>2}:{:0>2}", r.hours, r.minutes, r.seconds),
+                ),
+                ("minutes", status.remaining_minutes.to_string()),
+                ("percentage", status.percentage.to_string()),
+                ("class", status.class().to_string()),
+            ];
+
+            cli::render(template, &fields)
+        }
+    }
+}
+
+/// Prints the status once, or keeps printing it until stdout is closed.
+pub fn run(output: &Output, watch: bool) -> glib::ExitCode {
+    let mut cache = CacheJson::read();
+    let mut pref = match PreferencesJson::read() {
+        Ok(pref) => pref,
+        Err(e) => {
+            eprintln!("{e}");
+            return glib::ExitCode::FAILURE;
+        }
+    };
+    let mut loaded = Instant::now();
+
+    if !watch {
+        let now = Local::now().naive_local();
+        let Some(status) = status(&cache, &pref.preferences, now) else {
+            eprintln!("{}", cli::not_cached(now.date()));
+            return glib::ExitCode::FAILURE;
+        };
+
+        return match render(Some(&status), output) {
+            Ok(line) => {
+                println!("{line}");
+                glib::ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                glib::ExitCode::FAILURE
+            }
+        };
+    }
+
+    let mut stdout = io::stdout();
+    loop {
+        // The app may have fetched new times or changed the reminders meanwhile
+        if loaded.elapsed() >= RELOAD_INTERVAL {
+            cache = CacheJson::read();
+            if let Ok(new_pref) = PreferencesJson::read() {
+                pref = new_pref;
+            }
+            loaded = Instant::now();
+        }
+
+        let status = status(&cache, &pref.preferences, Local::now().naive_local());
+        let line = match render(status.as_ref(), output) {
+            Ok(line) => line,
+            Err(e) => {
+                eprintln!("{e}");
+                return glib::ExitCode::FAILURE;
+            }
+        };
+
+        // The bar has exited
+        if writeln!(stdout, "{line}")
+            .and_then(|_| stdout.flush())
+            .is_err()
+        {
+            return glib::ExitCode::SUCCESS;
+        }
+
+        thread::sleep(status.map_or(RELOAD_INTERVAL, |s| s.update_interval()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveTime};
+
+    use super::*;
+    use crate::cache::tests::cache_with_times;
+    use crate::preferences::tests::defaults;
+
+    fn status_at(time: &str) -> Option<Status> {
+        let today = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let cache = cache_with_times(today.pred_opt().unwrap(), today.succ_opt().unwrap());
+        let now = today.and_time(NaiveTime::parse_from_str(time, "%H:%M:%S").unwrap());
+
+        status(&cache, &defaults().preferences, now)
+    }
+
+    #[test]
+    fn counts_down_from_now() {
+        let status = status_at("11:30:20").unwrap();
+
+        assert_eq!(status.prayer, Prayer::Dhuhr);
+        assert_eq!(
+            (
+                status.remaining.hours,
+                status.remaining.minutes,
+                status.remaining.seconds
+            ),
+            (0, 29, 40)
+        );
+        assert_eq!(status.remaining_minutes, 30);
+        // 5 of the 5.5 hours since sunrise
+        assert_eq!(status.percentage, 91);
+        assert!(!status.warning);
+    }
+
+    #[test]
+    fn warns_inside_the_reminder() {
+        assert!(status_at("11:50:00").unwrap().warning);
+    }
+
+    #[test]
+    fn after_isha_counts_to_tomorrows_fajr() {
+        let status = status_at("23:00:00").unwrap();
+
+        assert_eq!(status.prayer, Prayer::Fajr);
+        assert_eq!(status.remaining_minutes, 6 * 60);
+    }
+}
@@ -0,0 +1,217 @@
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// File operations used while saving, so a failing disk can be injected.
+pub trait FileSystem {
+    fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+    /// Create (or truncate) `path`, write `contents` and fsync it.
+    fn write_synced(&self, path: &Path, contents: &[u8]) -> io::Result<()>;
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+    /// Fsync a directory so a rename inside it survives a crash.
+    fn sync_dir(&self, path: &Path) -> io::Result<()>;
+}
+
+pub struct RealFileSystem;
+
+impl FileSystem for RealFileSystem {
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir_all(path)
+    }
+
+    fn write_synced(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        file.write_all(contents)?;
+        file.sync_all()
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn sync_dir(&self, path: &Path) -> io::Result<()> {
+        File::open(path)?.sync_all()
+    }
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".tmp");
+
+    path.with_file_name(file_name)
+}
+
+/// Writes `contents` next to `path` and renames it over, so readers either see
+/// the old file or the new one but never a half written file.
+pub fn write_atomic(fs: &impl FileSystem, path: &Path, contents: &[u8]) -> io::Result<()> {
+    let parent = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    fs.create_dir_all(parent)?;
+
+    let tmp = temp_path(path);
+
+    if let Err(e) = fs
+        .write_synced(&tmp, contents)
+        .and_then(|_| fs.rename(&tmp, path))
+    {
+        // Don't leave the broken temp file around, the live file is untouched.
+        let _ = fs.remove_file(&tmp);
+        return Err(e);
+    }
+
+    fs.sync_dir(parent)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::{cell::RefCell, collections::HashMap};
+
+    use super::*;
+
+    /// Step of a save that fails in `FakeFileSystem`
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Step {
+        Write,
+        Fsync,
+        Rename,
+        SyncDir,
+    }
+
+    /// Keeps the files in memory, and fails at `fail_at` like a full or broken disk.
+    #[derive(Default)]
+    pub struct FakeFileSystem {
+        pub files: RefCell<HashMap<PathBuf, Vec<u8>>>,
+        pub fail_at: Option<Step>,
+    }
+
+    impl FakeFileSystem {
+        pub fn failing_at(step: Step) -> Self {
+            Self {
+                fail_at: Some(step),
+                ..Default::default()
+            }
+        }
+
+        pub fn with_file(self, path: &Path, contents: &[u8]) -> Self {
+            self.files
+                .borrow_mut()
+                .insert(path.to_path_buf(), contents.to_vec());
+            self
+        }
+
+        pub fn contents(&self, path: &Path) -> Option<Vec<u8>> {
+            self.files.borrow().get(path).cloned()
+        }
+
+        fn fail(&self, step: Step) -> io::Result<()> {
+            if self.fail_at == Some(step) {
+                return Err(io::Error::other(format!("{step:?} failed")));
+            }
+
+            Ok(())
+        }
+    }
+
+    impl FileSystem for FakeFileSystem {
+        fn create_dir_all(&self, _path: &Path) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn write_synced(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+            let mut files = self.files.borrow_mut();
+
+            if self.fail_at == Some(Step::Write) {
+                // The disk filled up halfway
+                files.insert(path.to_path_buf(), contents[..contents.len() / 2].to_vec());
+                return self.fail(Step::Write);
+            }
+
+            files.insert(path.to_path_buf(), contents.to_vec());
+            self.fail(Step::Fsync)
+        }
+
+        fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+            self.fail(Step::Rename)?;
+
+            let mut files = self.files.borrow_mut();
+            let contents = files.remove(from).ok_or(io::ErrorKind::NotFound)?;
+            files.insert(to.to_path_buf(), contents);
+
+            Ok(())
+        }
+
+        fn remove_file(&self, path: &Path) -> io::Result<()> {
+            match self.files.borrow_mut().remove(path) {
+                Some(_) => Ok(()),
+                None => Err(io::ErrorKind::NotFound.into()),
+            }
+        }
+
+        fn sync_dir(&self, _path: &Path) -> io::Result<()> {
+            self.fail(Step::SyncDir)
+        }
+    }
+
+    const PATH: &str = "/config/preferences.json";
+
+    #[test]
+    fn writes_a_new_file() {
+        let fs = FakeFileSystem::default();
+
+        write_atomic(&fs, Path::new(PATH), b"new").unwrap();
+
+        assert_eq!(fs.contents(Path::new(PATH)), Some(b"new".to_vec()));
+        assert_eq!(fs.files.borrow().len(), 1);
+    }
+
+    #[test]
+    fn replaces_the_old_file() {
+        let fs = FakeFileSystem::default().with_file(Path::new(PATH), b"old");
+
+        write_atomic(&fs, Path::new(PATH), b"new").unwrap();
+
+        assert_eq!(fs.contents(Path::new(PATH)), Some(b"new".to_vec()));
+        assert_eq!(fs.contents(&temp_path(Path::new(PATH))), None);
+    }
+
+    #[test]
+    fn failures_keep_the_old_file() {
+        for step in [Step::Write, Step::Fsync, Step::Rename] {
+            let fs = FakeFileSystem::failing_at(step).with_file(Path::new(PATH), b"old");
+
+            let e = write_atomic(&fs, Path::new(PATH), b"new contents").unwrap_err();
+
+            assert_eq!(e.to_string(), format!("{step:?} failed"));
+            assert_eq!(fs.contents(Path::new(PATH)), Some(b"old".to_vec()));
+            // The broken temp file is cleaned up
+            assert_eq!(fs.files.borrow().len(), 1, "after a failed {step:?}");
+        }
+    }
+
+    #[test]
+    fn failed_directory_sync_is_reported() {
+        let fs = FakeFileSystem::failing_at(Step::SyncDir).with_file(Path::new(PATH), b"old");
+
+        let e = write_atomic(&fs, Path::new(PATH), b"new").unwrap_err();
+
+        assert_eq!(e.to_string(), "SyncDir failed");
+        assert_eq!(fs.contents(Path::new(PATH)), Some(b"new".to_vec()));
+    }
+
+    #[test]
+    fn temp_file_is_next_to_the_file() {
+        assert_eq!(
+            temp_path(Path::new(PATH)),
+            Path::new("/config/preferences.json.tmp")
+        );
+    }
+}
@@ -1,11 +1,128 @@
+use std::sync::OnceLock;
+
 use async_channel::Sender;
-use gettextrs::gettext;
+use formatx::formatx;
+use gettextrs::{gettext, ngettext};
 use gtk::glib;
 
-use crate::TrayMessage;
+use crate::traypixmap::{self, IconState};
+use crate::{TrayMessage, APP_ID};
+
+/// Choices of the mute submenu
+const MUTE_HOURS: [u32; 4] = [1, 2, 4, 8];
+
+/// Handle of the running tray, to update what it shows.
+static HANDLE: OnceLock<ksni::blocking::Handle<MyTray>> = OnceLock::new();
+
+/// What the tray shows besides its static items.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrayStatus {
+    /// e.g. "to Asr 01:23:45"
+    pub next_prayer: Option<String>,
+    /// Today's prayer names & times, in order
+    pub times: Vec<(String, String)>,
+    /// Drawn instead of the app icon when the prayer times are known
+    pub icon: Option<IconState>,
+    /// e.g. "15:30", while alerts are muted
+    pub muted_until: Option<String>,
+    /// Labels & district IDs of the recent locations, the current one first
+    pub locations: Vec<(String, String)>,
+}
 
 pub struct MyTray {
     pub sender: Sender<TrayMessage>,
+    pub status: TrayStatus,
+}
+
+/// Keeps the handle of the tray once it is running.
+pub fn set_handle(handle: ksni::blocking::Handle<MyTray>) {
+    let _ = HANDLE.set(handle);
+}
+
+/// Shows `status` in the tray, if there is one.
+pub fn update_status(status: TrayStatus) {
+    if let Some(handle) = HANDLE.get() {
+        handle.update(|tray| tray.status = status);
+    }
+}
+
+impl MyTray {
+    fn send(&self, message: TrayMessage) {
+        let sender = self.sender.clone();
+
+        glib::spawn_future(async move {
+            sender.send(message).await.unwrap();
+        });
+    }
+
+    fn mute_item(&self) -> ksni::MenuItem<Self> {
+        use ksni::menu::*;
+
+        if let Some(until) = &self.status.muted_until {
+            return StandardItem {
+                label: formatx!(gettext("Unmute (muted until {time})"), time = until).unwrap(),
+                icon_name: "audio-volume-high-symbolic".into(),
+                activate: Box::new(|this: &mut Self| this.send(TrayMessage::Unmute)),
+                ..Default::default()
+            }
+            .into();
+        }
+
+        let choices = MUTE_HOURS
+            .into_iter()
+            .map(|hours| {
+                StandardItem {
+                    label: formatx!(
+                        ngettext("For {hours} hour", "For {hours} hours", hours),
+                        hours = hours
+                    )
+                    .unwrap(),
+                    activate: Box::new(move |this: &mut Self| this.send(TrayMessage::Mute(hours))),
+                    ..Default::default()
+                }
+                .into()
+            })
+            .collect();
+
+        SubMenu {
+            label: gettext("Mute"),
+            icon_name: "audio-volume-muted-symbolic".into(),
+            submenu: choices,
+            ..Default::default()
+        }
+        .into()
+    }
+
+    fn locations_item(&self) -> ksni::MenuItem<Self> {
+        use ksni::menu::*;
+
+        let options = self
+            .status
+            .locations
+            .iter()
+            .map(|(label, _)| RadioItem {
+                label: label.clone(),
+                ..Default::default()
+            })
+            .collect();
+
+        SubMenu {
+            label: gettext("Location"),
+            icon_name: "mark-location-symbolic".into(),
+            submenu: vec![RadioGroup {
+                selected: 0,
+                select: Box::new(|this: &mut Self, index| {
+                    if let Some((_, district_id)) = this.status.locations.get(index) {
+                        this.send(TrayMessage::SwitchLocation(district_id.clone()));
+                    }
+                }),
+                options,
+            }
+            .into()],
+            ..Default::default()
+        }
+        .into()
+    }
 }
 
 impl std::fmt::Debug for MyTray {
@@ -16,53 +133,129 @@ impl std::fmt::Debug for MyTray {
 
 impl ksni::Tray for MyTray {
     fn icon_name(&self) -> String {
-        "io.github.eminfedar.vaktisalah-gtk-rs".into()
+        // Trays prefer the named icon over the pixmap
+        match self.status.icon {
+            Some(_) => String::new(),
+            None => APP_ID.into(),
+        }
+    }
+    fn icon_pixmap(&self) -> Vec<ksni::Icon> {
+        let Some(state) = &self.status.icon else {
+            return Vec::new();
+        };
+
+        traypixmap::SIZES
+            .into_iter()
+            .map(|size| {
+                let pixmap = traypixmap::render(state, size);
+
+                ksni::Icon {
+                    width: pixmap.width as i32,
+                    height: pixmap.height as i32,
+                    data: pixmap.data,
+                }
+            })
+            .collect()
     }
     fn title(&self) -> String {
         "VaktiSalah".into()
     }
+    fn tool_tip(&self) -> ksni::ToolTip {
+        ksni::ToolTip {
+            icon_name: APP_ID.into(),
+            title: "Vakt-i Salah".into(),
+            description: self.status.next_prayer.clone().unwrap_or_default(),
+            ..Default::default()
+        }
+    }
     // NOTE: On some system trays, `id` is a required property to avoid unexpected behaviors
     fn id(&self) -> String {
         "io.github.eminfedar.vaktisalah-gtk-rs".into()
     }
 
     fn activate(&mut self, _x: i32, _y: i32) {
-        let sender = self.sender.clone();
-
-        glib::spawn_future(async move {
-            sender.send(TrayMessage::Activate).await.unwrap();
-        });
+        self.send(TrayMessage::Activate);
     }
 
     fn menu(&self) -> Vec<ksni::MenuItem<Self>> {
         use ksni::menu::*;
-        vec![
+
+        let mut items = Vec::new();
+
+        if let Some(next_prayer) = &self.status.next_prayer {
+            items.push(
+                StandardItem {
+                    label: next_prayer.clone(),
+                    enabled: false,
+                    ..Default::default()
+                }
+                .into(),
+            );
+        }
+
+        if !self.status.times.is_empty() {
+            let times = self
+                .status
+                .times
+                .iter()
+                .map(|(name, time)| {
+                    StandardItem {
+                        label: format!("{time}  {name}"),
+                        enabled: false,
+                        ..Default::default()
+                    }
+                    .into()
+                })
+                .collect();
+
+            items.push(
+                SubMenu {
+                    label: gettext("Today's Prayer Times"),
+                    icon_name: "alarm-symbolic".into(),
+                    submenu: times,
+                    ..Default::default()
+                }
+                .into(),
+            );
+        }
+
+        if !items.is_empty() {
+            items.push(MenuItem::Separator);
+        }
+
+        // Quick actions
+        items.push(self.mute_item());
+        items.push(
+            StandardItem {
+                label: gettext("Update Prayer Times"),
+                icon_name: "view-refresh-symbolic".into(),
+                activate: Box::new(|this: &mut Self| this.send(TrayMessage::UpdatePrayerTimes)),
+                ..Default::default()
+            }
+            .into(),
+        );
+        if self.status.locations.len() > 1 {
+            items.push(self.locations_item());
+        }
+        items.push(MenuItem::Separator);
+
+        items.extend([
             StandardItem {
                 label: gettext("Show"),
                 icon_name: "view-fullscreen-symbolic".into(),
-                activate: Box::new(|this: &mut Self| {
-                    let sender = this.sender.clone();
-
-                    glib::spawn_future(async move {
-                        sender.send(TrayMessage::Activate).await.unwrap();
-                    });
-                }),
+                activate: Box::new(|this: &mut Self| this.send(TrayMessage::Activate)),
                 ..Default::default()
             }
             .into(),
             StandardItem {
                 label: gettext("Exit"),
                 icon_name: "application-exit-symbolic".into(),
-                activate: Box::new(|this: &mut Self| {
-                    let sender = this.sender.clone();
-
-                    glib::spawn_future(async move {
-                        sender.send(TrayMessage::Exit).await.unwrap();
-                    });
-                }),
+                activate: Box::new(|this: &mut Self| this.send(TrayMessage::Exit)),
                 ..Default::default()
             }
             .into(),
-        ]
+        ]);
+
+        items
     }
 }
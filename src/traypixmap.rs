@@ -0,0 +1,341 @@
+use std::f64::consts::TAU;
+
+/// Widths & heights the icon is rendered in, the tray picks the closest one.
+pub const SIZES: [usize; 3] = [22, 32, 48];
+
+/// The sizes below are for this icon size & scaled to the others.
+const BASE_SIZE: f64 = 32.0;
+/// Middle of the progress ring
+const RING_RADIUS: f64 = 14.0;
+const RING_WIDTH: f64 = 3.0;
+/// Badge inside the ring, behind the minutes
+const BADGE_RADIUS: f64 = 10.5;
+
+const TRACK_COLOR: Rgb = [0x9a, 0x99, 0x96];
+const TRACK_ALPHA: f64 = 0.4;
+const NORMAL_COLOR: Rgb = [0x35, 0x84, 0xe4];
+const WARNING_COLOR: Rgb = [0xe6, 0x61, 0x00];
+const TEXT_COLOR: Rgb = [0xff, 0xff, 0xff];
+
+type Rgb = [u8; 3];
+
+const GLYPH_WIDTH: usize = 3;
+const GLYPH_HEIGHT: usize = 5;
+
+/// 3x5 bitmaps of the digits & "h", a row per byte from the top.
+const GLYPHS: [(char, [u8; GLYPH_HEIGHT]); 11] = [
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b111, 0b001, 0b111, 0b100, 0b111]),
+    ('3', [0b111, 0b001, 0b111, 0b001, 0b111]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('6', [0b111, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b010, 0b010, 0b010]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b111]),
+    ('h', [0b100, 0b100, 0b111, 0b101, 0b101]),
+];
+
+/// What the tray icon shows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IconState {
+    pub remaining_minutes: u32,
+    /// Part of the time between the previous & next prayer that has passed, from 0.0 to 1.0
+    pub progress: f64,
+    /// Whether a reminder of the next prayer has already been due
+    pub warning: bool,
+}
+
+/// An image in ARGB32 with straight alpha, in network byte order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pixmap {
+    pub width: usize,
+    pub height: usize,
+    pub data: Vec<u8>,
+}
+
+impl Pixmap {
+    /// A fully transparent image
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            data: vec![0; width * height * 4],
+        }
+    }
+
+    /// Draws `color` over the pixel with `alpha` coverage.
+    fn blend(&mut self, x: usize, y: usize, color: Rgb, alpha: f64) {
+        if alpha <= 0.0 || x >= self.width || y >= self.height {
+            return;
+        }
+
+        let i = (y * self.width + x) * 4;
+        let alpha = alpha.min(1.0);
+        let below = f64::from(self.data[i]) / 255.0;
+        let out = alpha + below * (1.0 - alpha);
+
+        for (c, channel) in color.iter().enumerate() {
+            let src = f64::from(*channel);
+            let dst = f64::from(self.data[i + 1 + c]);
+
+            self.data[i + 1 + c] =
+                ((src * alpha + dst * below * (1.0 - alpha)) / out).round() as u8;
+        }
+        self.data[i] = (out * 255.0).round() as u8;
+    }
+}
+
+fn glyph(c: char) -> Option<&'static [u8; GLYPH_HEIGHT]> {
+    GLYPHS.iter().find(|(g, _)| *g == c).map(|(_, rows)| rows)
+}
+
+/// Minutes below 100, whole hours otherwise, e.g. "45" or "3h".
+pub fn label(remaining_minutes: u32) -> String {
+    if remaining_minutes < 100 {
+        remaining_minutes.to_string()
+    } else {
+        format!("{}h", remaining_minutes / 60)
+    }
+}
+
+/// Writes `text` centered, as large as it fits in the badge.
+fn draw_text(pixmap: &mut Pixmap, text: &str, color: Rgb) {
+    let glyphs: Vec<_> = text.chars().filter_map(glyph).collect();
+    if glyphs.is_empty() {
+        return;
+    }
+
+    let size = pixmap.width;
+    let unit = size as f64 / BASE_SIZE;
+    let width_at = |scale: usize| glyphs.len() * (GLYPH_WIDTH + 1) * scale - scale;
+
+    let large = ((unit * 2.0).round() as usize).max(1);
+    let scale = if width_at(large) as f64 <= (BADGE_RADIUS * 2.0 - 4.0) * unit {
+        large
+    } else {
+        (unit.round() as usize).max(1)
+    };
+
+    let left = (size - width_at(scale)) / 2;
+    let top = (size - GLYPH_HEIGHT * scale) / 2;
+
+    for (n, rows) in glyphs.iter().enumerate() {
+        let glyph_left = left + n * (GLYPH_WIDTH + 1) * scale;
+
+        for (row, bits) in rows.iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - column)) == 0 {
+                    continue;
+                }
+
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        let x = glyph_left + column * scale + dx;
+                        let y = top + row * scale + dy;
+                        pixmap.blend(x, y, color, 1.0);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A ring filling up clockwise from the top until the next prayer, around a
+/// badge with the remaining time. Orange in the warning window, blue otherwise.
+pub fn render(state: &IconState, size: usize) -> Pixmap {
+    let mut pixmap = Pixmap::new(size, size);
+    let unit = size as f64 / BASE_SIZE;
+    let center = size as f64 / 2.0;
+    let (ring_radius, ring_width) = (RING_RADIUS * unit, RING_WIDTH * unit);
+    let badge_radius = BADGE_RADIUS * unit;
+    let color = if state.warning {
+        WARNING_COLOR
+    } else {
+        NORMAL_COLOR
+    };
+    let progress = state.progress.clamp(0.0, 1.0);
+
+    for y in 0..size {
+        for x in 0..size {
+            // Sampled at the center of the pixel
+            let dx = x as f64 + 0.5 - center;
+            let dy = y as f64 + 0.5 - center;
+            let distance = dx.hypot(dy);
+
+            // Antialiased edges from the distance to them
+            let ring = (ring_width / 2.0 + 0.5 - (distance - ring_radius).abs()).min(1.0);
+            if ring > 0.0 {
+                let angle = dx.atan2(-dy).rem_euclid(TAU);
+
+                if angle / TAU <= progress {
+                    pixmap.blend(x, y, color, ring);
+                } else {
+                    pixmap.blend(x, y, TRACK_COLOR, ring * TRACK_ALPHA);
+                }
+            }
+
+            let badge = badge_radius + 0.5 - distance;
+            pixmap.blend(x, y, color, badge);
+        }
+    }
+
+    draw_text(&mut pixmap, &label(state.remaining_minutes), TEXT_COLOR);
+
+    pixmap
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRANSPARENT: [u8; 4] = [0; 4];
+    const TRACK: [u8; 4] = [102, 0x9a, 0x99, 0x96];
+
+    fn state(progress: f64, warning: bool) -> IconState {
+        IconState {
+            remaining_minutes: 8,
+            progress,
+            warning,
+        }
+    }
+
+    fn opaque([r, g, b]: Rgb) -> [u8; 4] {
+        [0xff, r, g, b]
+    }
+
+    fn pixel(pixmap: &Pixmap, x: usize, y: usize) -> [u8; 4] {
+        let i = (y * pixmap.width + x) * 4;
+        pixmap.data[i..i + 4].try_into().unwrap()
+    }
+
+    /// The pixel on the middle of the ring, `turn` of the way clockwise from the top.
+    fn ring_pixel(pixmap: &Pixmap, turn: f64) -> [u8; 4] {
+        let unit = pixmap.width as f64 / BASE_SIZE;
+        let center = pixmap.width as f64 / 2.0;
+        let angle = turn * TAU;
+
+        let x = center + RING_RADIUS * unit * angle.sin();
+        let y = center - RING_RADIUS * unit * angle.cos();
+        pixel(pixmap, x as usize, y as usize)
+    }
+
+    /// Above the text, inside the badge
+    fn badge_pixel(pixmap: &Pixmap) -> [u8; 4] {
+        let unit = pixmap.width as f64 / BASE_SIZE;
+        let center = pixmap.width as f64 / 2.0;
+
+        pixel(
+            pixmap,
+            center as usize,
+            (center - BADGE_RADIUS * unit * 0.7) as usize,
+        )
+    }
+
+    #[test]
+    fn pixels_are_argb() {
+        for size in SIZES {
+            let pixmap = render(&state(0.5, false), size);
+
+            assert_eq!((pixmap.width, pixmap.height), (size, size));
+            assert_eq!(pixmap.data.len(), size * size * 4);
+            // Alpha first, then red, green & blue
+            assert_eq!(badge_pixel(&pixmap), [0xff, 0x35, 0x84, 0xe4], "{size}");
+        }
+    }
+
+    #[test]
+    fn corners_are_transparent() {
+        for size in SIZES {
+            let pixmap = render(&state(1.0, true), size);
+
+            for (x, y) in [(0, 0), (size - 1, 0), (0, size - 1), (size - 1, size - 1)] {
+                assert_eq!(pixel(&pixmap, x, y), TRANSPARENT, "{size}");
+            }
+        }
+    }
+
+    #[test]
+    fn ring_fills_clockwise_from_the_top() {
+        for size in SIZES {
+            let empty = render(&state(0.0, false), size);
+            let half = render(&state(0.5, false), size);
+            let full = render(&state(1.0, false), size);
+
+            for turn in [0.25, 0.75] {
+                assert_eq!(ring_pixel(&empty, turn), TRACK, "{size} {turn}");
+                assert_eq!(
+                    ring_pixel(&full, turn),
+                    opaque(NORMAL_COLOR),
+                    "{size} {turn}"
+                );
+            }
+            assert_eq!(ring_pixel(&half, 0.25), opaque(NORMAL_COLOR), "{size}");
+            assert_eq!(ring_pixel(&half, 0.75), TRACK, "{size}");
+        }
+    }
+
+    #[test]
+    fn warning_is_orange() {
+        for size in SIZES {
+            let pixmap = render(&state(0.5, true), size);
+
+            assert_eq!(badge_pixel(&pixmap), opaque(WARNING_COLOR), "{size}");
+            assert_eq!(ring_pixel(&pixmap, 0.25), opaque(WARNING_COLOR), "{size}");
+        }
+    }
+
+    #[test]
+    fn progress_is_clamped() {
+        for size in SIZES {
+            assert_eq!(
+                render(&state(-0.5, false), size),
+                render(&state(0.0, false), size)
+            );
+            assert_eq!(
+                render(&state(1.5, false), size),
+                render(&state(1.0, false), size)
+            );
+        }
+    }
+
+    #[test]
+    fn label_is_white_inside_the_badge() {
+        for size in SIZES {
+            let white_pixels = |minutes| {
+                let pixmap = render(
+                    &IconState {
+                        remaining_minutes: minutes,
+                        ..state(0.5, false)
+                    },
+                    size,
+                );
+                let center = size as f64 / 2.0;
+                let badge_radius = BADGE_RADIUS * size as f64 / BASE_SIZE;
+
+                (0..size)
+                    .flat_map(|y| (0..size).map(move |x| (x, y)))
+                    .filter(|&(x, y)| pixel(&pixmap, x, y) == opaque(TEXT_COLOR))
+                    .inspect(|&(x, y)| {
+                        let distance = (x as f64 + 0.5 - center).hypot(y as f64 + 0.5 - center);
+                        assert!(distance < badge_radius, "{size} {minutes}");
+                    })
+                    .count()
+            };
+
+            // "8" lights more of its bitmap than "1", "3h" & "45" are two glyphs
+            assert!(white_pixels(8) > white_pixels(1), "{size}");
+            assert!(white_pixels(180) > 0, "{size}");
+            assert!(white_pixels(45) > white_pixels(8), "{size}");
+        }
+    }
+
+    #[test]
+    fn labels() {
+        assert_eq!(label(0), "0");
+        assert_eq!(label(99), "99");
+        assert_eq!(label(100), "1h");
+        assert_eq!(label(185), "3h");
+    }
+}
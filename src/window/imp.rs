@@ -1,23 +1,33 @@
 use std::cell::{Cell, RefCell};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
 
 use adw::prelude::*;
 use adw::subclass::prelude::*;
 use async_channel::Sender;
+use chrono::NaiveDateTime;
 use gtk::glib;
 
 use gtk::StringList;
 
+use crate::cache::CacheJson;
+use crate::networking::LocationLists;
 use crate::prayer::PrayerTimesWithDate;
+use crate::prayernotificationrow::PrayerNotificationRow;
+use crate::prayeroverlay::PrayerOverlay;
 use crate::preferences::PreferencesJson;
 use crate::rowprayertime::RowPrayerTime;
+use crate::scheduler::Scheduler;
+use crate::trayicon::TrayStatus;
 
 #[derive(Debug)]
 pub enum Message {
-    CityListArrived(Result<HashMap<String, String>, reqwest::Error>, String),
-    DistrictListArrived(Result<HashMap<String, String>, reqwest::Error>, String),
+    CityList(Result<HashMap<String, String>, reqwest::Error>, String),
+    DistrictList(Result<HashMap<String, String>, reqwest::Error>, String),
 
-    PrayerTimesArrived(Result<Vec<PrayerTimesWithDate>, reqwest::Error>),
+    LocationLists(Result<LocationLists, reqwest::Error>),
+
+    PrayerTimes(Result<Vec<PrayerTimesWithDate>, reqwest::Error>),
 }
 
 #[derive(Default, gtk::CompositeTemplate, glib::Properties)]
@@ -36,15 +46,83 @@ pub struct MainWindow {
     #[template_child]
     pub main_page: TemplateChild<adw::NavigationPage>,
 
+    #[template_child]
+    pub reminders_page: TemplateChild<adw::PreferencesPage>,
+
+    #[template_child]
+    pub play_sound_row: TemplateChild<adw::SwitchRow>,
+
+    #[template_child]
+    pub raise_mode_row: TemplateChild<adw::ComboRow>,
+
+    #[template_child]
+    pub fullscreen_overlay_row: TemplateChild<adw::SwitchRow>,
+
+    #[template_child]
+    pub pause_media_row: TemplateChild<adw::SwitchRow>,
+
+    #[template_child]
+    pub run_at_login_row: TemplateChild<adw::SwitchRow>,
+
+    #[template_child]
+    pub quiet_hours_row: TemplateChild<adw::ExpanderRow>,
+
+    #[template_child]
+    pub quiet_hours_start_row: TemplateChild<adw::SpinRow>,
+
+    #[template_child]
+    pub quiet_hours_end_row: TemplateChild<adw::SpinRow>,
+
+    #[template_child]
+    pub notification_row_fajr: TemplateChild<PrayerNotificationRow>,
+
+    #[template_child]
+    pub notification_row_sunrise: TemplateChild<PrayerNotificationRow>,
+
+    #[template_child]
+    pub notification_row_dhuhr: TemplateChild<PrayerNotificationRow>,
+
+    #[template_child]
+    pub notification_row_asr: TemplateChild<PrayerNotificationRow>,
+
+    #[template_child]
+    pub notification_row_maghrib: TemplateChild<PrayerNotificationRow>,
+
+    #[template_child]
+    pub notification_row_isha: TemplateChild<PrayerNotificationRow>,
+
     pub preferences: RefCell<PreferencesJson>,
+    pub cache: RefCell<CacheJson>,
 
     // Prayer times
     pub todays_prayers: RefCell<Option<PrayerTimesWithDate>>,
     pub tomorrows_prayers: RefCell<Option<PrayerTimesWithDate>>,
     pub visible_day: RefCell<i8>,
+    // Finds the reminders & prayer times that arrived since the last tick
+    pub scheduler: RefCell<Scheduler>,
+    // Prayer times marked as prayed from notifications, silencing later reminders
+    pub prayed: RefCell<HashSet<NaiveDateTime>>,
+    // Groups of the reminders page, indexed by `Prayer::index()`
+    pub reminder_groups: RefCell<Vec<adw::PreferencesGroup>>,
+    pub reminder_rows: RefCell<Vec<Vec<adw::ActionRow>>>,
+    // Fullscreen overlay of the last alert, until it is dismissed
+    pub overlay: glib::WeakRef<PrayerOverlay>,
+    // Last status shown in the tray & when it was checked
+    pub tray_status: RefCell<TrayStatus>,
+    pub tray_updated: Cell<Option<Instant>>,
+    // Alerts are silent until then, set from the tray
+    pub muted_until: Cell<Option<NaiveDateTime>>,
 
     pub sender: RefCell<Option<Sender<Message>>>,
 
+    // Pending debounced preferences save
+    pub save_source: RefCell<Option<glib::SourceId>>,
+    // The preferences as last saved or reloaded, to merge unsaved changes with outside ones
+    pub stored_preferences: RefCell<Option<PreferencesJson>>,
+    // Follows changes of the stored preferences & pending reload
+    pub preferences_watcher: RefCell<Option<glib::Object>>,
+    pub reload_source: RefCell<Option<glib::SourceId>>,
+
     // Date
     #[property(get, set)]
     pub gregorian_date: RefCell<String>,
@@ -76,10 +154,6 @@ pub struct MainWindow {
     #[property(get, set)]
     pub current_prayer: Cell<i32>,
 
-    // Settings:
-    #[property(get, set)]
-    pub warn_min: Cell<f64>,
-
     // Models
     #[property(get, set)]
     pub model_country: RefCell<StringList>,
@@ -108,6 +182,20 @@ pub struct MainWindow {
     pub selected_district_index: Cell<i32>,
 }
 
+impl MainWindow {
+    /// Notification rows of the settings page, indexed by `Prayer::index()`
+    pub fn notification_rows(&self) -> [&PrayerNotificationRow; 6] {
+        [
+            &self.notification_row_fajr,
+            &self.notification_row_sunrise,
+            &self.notification_row_dhuhr,
+            &self.notification_row_asr,
+            &self.notification_row_maghrib,
+            &self.notification_row_isha,
+        ]
+    }
+}
+
 #[glib::object_subclass]
 impl ObjectSubclass for MainWindow {
     const NAME: &'static str = "MainWindow";
@@ -117,6 +205,13 @@ impl ObjectSubclass for MainWindow {
     fn class_init(klass: &mut Self::Class) {
         klass.bind_template();
         klass.bind_template_instance_callbacks();
+
+        klass.install_action_async("win.export-settings", None, |window, _, _| async move {
+            window.export_settings().await;
+        });
+        klass.install_action_async("win.import-settings", None, |window, _, _| async move {
+            window.import_settings().await;
+        });
     }
 
     fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
@@ -1,37 +1,51 @@
 mod imp;
+mod notifications;
+mod reminders;
+mod service;
+mod tray;
+
+pub use notifications::install_notification_actions;
 
 use std::collections::HashMap;
+use std::time::Duration;
 
+use adw::prelude::{AlertDialogExt, AlertDialogExtManual, ComboRowExt, ExpanderRowExt};
 use adw::subclass::prelude::ObjectSubclassIsExt;
 use adw::ButtonRow;
 use adw::ComboRow;
-use adw::SpinRow;
 use async_channel::Receiver;
 use chrono::Local;
 use chrono::Locale;
 use gtk::gio;
-use gtk::gio::prelude::ApplicationExt;
-use gtk::gio::Notification;
+use gtk::gio::prelude::FileExtManual;
 use gtk::glib;
-use gtk::glib::object::ObjectExt;
+use gtk::glib::object::{Cast, ObjectExt};
 use gtk::glib::ParamSpec;
-use gtk::prelude::GtkWindowExt;
+use gtk::prelude::{GtkApplicationExt, GtkWindowExt};
 
 use gtk::Button;
 use gtk::StringList;
 
-use formatx::formatx;
 use gettextrs::gettext;
 use gtk::StringObject;
 use imp::Message;
 
+use crate::autostart;
+use crate::bundle::{self, SettingsBundle};
 use crate::networking;
 use crate::prayer;
-
-use crate::sound::play_alert;
+use crate::prayer::Prayer;
+use crate::prayernotificationrow::PrayerNotificationRow;
+use crate::preferences::PreferencesJson;
+use crate::preferences::RaiseMode;
 use crate::LOCALE;
 use crate::RUNTIME;
 
+/// Bursts of setting changes (e.g. spin button steps) are written once after this delay.
+const SAVE_DELAY: Duration = Duration::from_millis(500);
+/// Change events arriving within this delay are reloaded together.
+const RELOAD_DELAY: Duration = Duration::from_millis(200);
+
 glib::wrapper! {
     pub struct MainWindow(ObjectSubclass<imp::MainWindow>)
     @extends adw::ApplicationWindow, gtk::ApplicationWindow, gtk::Window, gtk::Widget,
@@ -55,6 +69,12 @@ fn fill_model(
     (string_list, selected_index)
 }
 
+pub fn main_window(app: &adw::Application) -> Option<MainWindow> {
+    app.windows()
+        .into_iter()
+        .find_map(|w| w.downcast::<MainWindow>().ok())
+}
+
 #[gtk::template_callbacks]
 impl MainWindow {
     pub fn new(app: &adw::Application) -> Self {
@@ -64,8 +84,25 @@ impl MainWindow {
 
         let (tx, rx) = async_channel::bounded(4);
         imp.sender.replace(Some(tx));
+        imp.stored_preferences
+            .replace(Some(imp.preferences.borrow().clone()));
 
         new_self.listen_channel_message(rx);
+        new_self.build_reminders_page();
+
+        new_self.watch_preferences();
+        new_self.watch_sleep();
+
+        new_self.connect_close_request(|window| {
+            let (width, height) = window.default_size();
+
+            let pref = &window.imp().preferences.borrow().preferences;
+            pref.window_width.replace(width);
+            pref.window_height.replace(height);
+            window.schedule_save_preferences();
+
+            glib::Propagation::Proceed
+        });
 
         new_self
     }
@@ -73,23 +110,70 @@ impl MainWindow {
     pub fn read_preferences(&self) {
         let imp = self.imp();
         let pref = imp.preferences.borrow();
+        let cache = imp.cache.borrow();
 
         self.update_model_country(
-            pref.countries.borrow().clone(),
-            pref.countries_en.borrow().clone(),
+            cache.countries.borrow().clone(),
+            cache.countries_en.borrow().clone(),
             Some(pref.preferences.country.borrow().clone()),
         );
         self.update_model_city(
-            pref.cities.borrow().clone(),
+            cache.cities.borrow().clone(),
             Some(pref.preferences.city.borrow().clone()),
         );
         let district = pref.preferences.district.borrow();
-        self.update_model_district(pref.districts.borrow().clone(), Some(district.clone()));
+        self.update_model_district(cache.districts.borrow().clone(), Some(district.clone()));
         self.set_district_title(district.clone());
 
-        // Warn Min
-        let warn_min = *pref.preferences.warning_minutes.borrow();
-        self.set_warn_min(f64::from(warn_min));
+        // Restore city & district lists if the cache is cleared or outdated
+        if cache.is_locations_expired() || !cache.districts.borrow().contains_key(&*district) {
+            self.refresh_location_lists();
+        }
+
+        // Reminders
+        self.update_reminders_page();
+
+        // Notification options
+        imp.play_sound_row
+            .set_active(*pref.preferences.play_sound.borrow());
+        imp.raise_mode_row
+            .set_selected(pref.preferences.raise_mode.borrow().index());
+        imp.fullscreen_overlay_row
+            .set_active(*pref.preferences.fullscreen_overlay.borrow());
+        imp.pause_media_row
+            .set_active(*pref.preferences.pause_media.borrow());
+        imp.run_at_login_row
+            .set_active(*pref.preferences.run_at_login.borrow());
+
+        // Quiet hours
+        imp.quiet_hours_row
+            .set_enable_expansion(*pref.preferences.quiet_hours.borrow());
+        imp.quiet_hours_start_row
+            .set_value(f64::from(*pref.preferences.quiet_hours_start.borrow()));
+        imp.quiet_hours_end_row
+            .set_value(f64::from(*pref.preferences.quiet_hours_end.borrow()));
+
+        // Per prayer notifications
+        for (prayer, row) in Prayer::ALL.iter().zip(imp.notification_rows()) {
+            row.set_notify_before(pref.preferences.notify_before(*prayer));
+            row.set_notify_at_time(pref.preferences.notify_at_time(*prayer));
+            row.set_sound(
+                pref.preferences
+                    .prayer_sound(*prayer)
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+            );
+            row.set_volume(pref.preferences.volume(*prayer));
+            row.set_fade_in(pref.preferences.fade_in(*prayer));
+            row.set_silent(pref.preferences.silent(*prayer));
+            row.set_override_dnd(pref.preferences.override_dnd(*prayer));
+        }
+
+        // Window size
+        self.set_default_size(
+            *pref.preferences.window_width.borrow(),
+            *pref.preferences.window_height.borrow(),
+        );
 
         // Set Prayer Time Labels:
         self.update_prayer_time_labels();
@@ -97,11 +181,11 @@ impl MainWindow {
 
     pub fn update_prayer_time_labels(&self) {
         let imp = self.imp();
-        let pref = imp.preferences.borrow();
+        let cache = imp.cache.borrow();
 
         // Read Today's Prayers:
-        let todays_prayers = prayer::get_prayers_of_day(&pref, 0);
-        let tomorrows_prayers = prayer::get_prayers_of_day(&pref, 1);
+        let todays_prayers = prayer::get_prayers_of_day(&cache, 0);
+        let tomorrows_prayers = prayer::get_prayers_of_day(&cache, 1);
 
         // Set labels
         if let Some(today) = todays_prayers.clone() {
@@ -179,31 +263,368 @@ impl MainWindow {
         self.set_selected_district_index(selected_index);
     }
 
+    /// Saves the preferences after `SAVE_DELAY`, restarting the delay on every call.
+    pub fn schedule_save_preferences(&self) {
+        let imp = self.imp();
+        let self_clone = self.downgrade();
+
+        let source = glib::timeout_add_local_once(SAVE_DELAY, move || {
+            if let Some(self_clone) = self_clone.upgrade() {
+                self_clone.imp().save_source.take();
+                self_clone.save_preferences();
+            }
+        });
+
+        if let Some(old_source) = imp.save_source.replace(Some(source)) {
+            old_source.remove();
+        }
+    }
+
+    /// Writes a pending debounced save immediately, e.g. before quitting.
+    pub fn flush_pending_save(&self) {
+        if let Some(source) = self.imp().save_source.take() {
+            source.remove();
+            self.save_preferences();
+        }
+    }
+
+    pub fn save_preferences(&self) {
+        let imp = self.imp();
+
+        let pref = imp.preferences.borrow();
+
+        match pref.save() {
+            Ok(()) => {
+                imp.stored_preferences.replace(Some(pref.clone()));
+            }
+            Err(e) => {
+                eprintln!("Failed to save preferences: {e}");
+
+                let toast = adw::Toast::new(&gettext("Couldn't save preferences!"));
+                imp.toast_overlay.add_toast(toast);
+            }
+        }
+    }
+
+    /// Follows preferences changed outside of the app (synced files, scripts, `gsettings`).
+    pub fn watch_preferences(&self) {
+        let self_clone = self.downgrade();
+
+        let watcher = PreferencesJson::watch(move || {
+            if let Some(self_clone) = self_clone.upgrade() {
+                self_clone.schedule_reload_preferences();
+            }
+        });
+
+        self.imp().preferences_watcher.replace(watcher);
+    }
+
+    /// Reloads once after a burst of change events (e.g. one per GSettings key).
+    fn schedule_reload_preferences(&self) {
+        let imp = self.imp();
+        let self_clone = self.downgrade();
+
+        let source = glib::timeout_add_local_once(RELOAD_DELAY, move || {
+            if let Some(self_clone) = self_clone.upgrade() {
+                self_clone.imp().reload_source.take();
+                self_clone.reload_preferences();
+            }
+        });
+
+        if let Some(old_source) = imp.reload_source.replace(Some(source)) {
+            old_source.remove();
+        }
+    }
+
+    pub fn reload_preferences(&self) {
+        let imp = self.imp();
+
+        let stored = match PreferencesJson::reload() {
+            Ok(p) => p,
+            Err(e) => {
+                eprintln!("Rejected changed preferences: {e}");
+
+                let toast = adw::Toast::new(&gettext("Changed preferences are invalid, ignored."));
+                imp.toast_overlay.add_toast(toast);
+                return;
+            }
+        };
+
+        let base = imp.stored_preferences.replace(Some(stored.clone()));
+
+        // Changes still waiting for the debounced save win over the reloaded values
+        let new_pref = match imp.save_source.take() {
+            Some(source) => {
+                source.remove();
+                let base = base.unwrap_or_else(|| stored.clone());
+                imp.preferences.borrow().merge(&base, &stored)
+            }
+            None => stored.clone(),
+        };
+        let unsaved = new_pref != stored;
+
+        // Our own saves come back here too
+        if *imp.preferences.borrow() == new_pref {
+            if unsaved {
+                self.save_preferences();
+            }
+            return;
+        }
+
+        eprintln!("Preferences changed, reloading...");
+
+        let district_id = new_pref.preferences.district_id.borrow().clone();
+        if self.apply_preferences(new_pref) {
+            self.request_prayer_times(district_id);
+        }
+        if unsaved {
+            self.save_preferences();
+        }
+
+        let toast = adw::Toast::new(&gettext("Preferences reloaded."));
+        imp.toast_overlay.add_toast(toast);
+    }
+
+    /// Replaces the preferences and updates the window. Returns whether the location changed.
+    fn apply_preferences(&self, new_pref: PreferencesJson) -> bool {
+        let imp = self.imp();
+
+        let location_changed = *imp.preferences.borrow().preferences.district_id.borrow()
+            != *new_pref.preferences.district_id.borrow();
+
+        imp.preferences.replace(new_pref);
+        self.read_preferences();
+
+        location_changed
+    }
+
+    fn json_file_filter() -> gio::ListStore {
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some(&gettext("Settings File")));
+        filter.add_suffix("json");
+
+        let filters = gio::ListStore::new::<gtk::FileFilter>();
+        filters.append(&filter);
+
+        filters
+    }
+
+    pub async fn export_settings(&self) {
+        let imp = self.imp();
+
+        let dialog = adw::AlertDialog::new(
+            Some(&gettext("Export Settings")),
+            Some(&gettext(
+                "Cached prayer times can be included, so they don't have to be downloaded again.",
+            )),
+        );
+        dialog.add_responses(&[
+            ("cancel", &gettext("Cancel")),
+            ("settings", &gettext("Settings Only")),
+            ("with-times", &gettext("Include Prayer Times")),
+        ]);
+        dialog.set_response_appearance("with-times", adw::ResponseAppearance::Suggested);
+        dialog.set_close_response("cancel");
+
+        let response = dialog.choose_future(self).await;
+        if response == "cancel" {
+            return;
+        }
+
+        let file_dialog = gtk::FileDialog::builder()
+            .title(gettext("Export Settings"))
+            .initial_name("vaktisalah-settings.json")
+            .filters(&Self::json_file_filter())
+            .modal(true)
+            .build();
+
+        // Dismissed
+        let Ok(file) = file_dialog.save_future(Some(self)).await else {
+            return;
+        };
+
+        let prayer_times =
+            (response == "with-times").then(|| imp.cache.borrow().prayer_times.borrow().clone());
+        let bundle = SettingsBundle::new(&imp.preferences.borrow().preferences, prayer_times);
+
+        let result = match bundle.to_json() {
+            Ok(json) => file
+                .replace_contents_future(
+                    json,
+                    None,
+                    false,
+                    gio::FileCreateFlags::REPLACE_DESTINATION,
+                )
+                .await
+                .map(|_| ())
+                .map_err(|(_, e)| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+
+        let toast = match result {
+            Ok(_) => adw::Toast::new(&gettext("Settings exported.")),
+            Err(e) => {
+                eprintln!("Failed to export settings: {e}");
+                adw::Toast::new(&gettext("Couldn't export settings!"))
+            }
+        };
+        imp.toast_overlay.add_toast(toast);
+    }
+
+    pub async fn import_settings(&self) {
+        let imp = self.imp();
+
+        let file_dialog = gtk::FileDialog::builder()
+            .title(gettext("Import Settings"))
+            .filters(&Self::json_file_filter())
+            .modal(true)
+            .build();
+
+        // Dismissed
+        let Ok(file) = file_dialog.open_future(Some(self)).await else {
+            return;
+        };
+
+        let bundle = match file.load_contents_future().await {
+            Ok((bytes, _)) => SettingsBundle::from_json(&String::from_utf8_lossy(&bytes)),
+            Err(e) => Err(e.to_string()),
+        };
+        let bundle = match bundle {
+            Ok(b) => b,
+            Err(e) => {
+                eprintln!("Failed to import settings: {e}");
+
+                let toast = adw::Toast::new(&gettext("Couldn't import settings!"));
+                imp.toast_overlay.add_toast(toast);
+                return;
+            }
+        };
+
+        let (new_preferences, skipped_sounds) =
+            bundle.preferences_for(&imp.preferences.borrow().preferences, |path| path.exists());
+        let changes = bundle::diff(&imp.preferences.borrow().preferences, &new_preferences);
+
+        if changes.is_empty() && skipped_sounds.is_empty() && bundle.prayer_times.is_none() {
+            let toast = adw::Toast::new(&gettext("Settings are already the same."));
+            imp.toast_overlay.add_toast(toast);
+            return;
+        }
+
+        // Show what will change before applying
+        let diff_text = changes
+            .iter()
+            .map(|c| format!("- {}: {}\n+ {}: {}", c.key, c.old, c.key, c.new))
+            .chain(skipped_sounds.iter().map(|c| {
+                format!(
+                    "! {}: {} ({})",
+                    c.key,
+                    c.new,
+                    gettext("not applied, machine-local")
+                )
+            }))
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        let body = if bundle.prayer_times.is_some() {
+            gettext("Cached prayer times will be replaced too.")
+        } else {
+            String::new()
+        };
+
+        let dialog = adw::AlertDialog::new(Some(&gettext("Import Settings?")), Some(&body));
+        dialog.add_responses(&[
+            ("cancel", &gettext("Cancel")),
+            ("import", &gettext("Import")),
+        ]);
+        dialog.set_response_appearance("import", adw::ResponseAppearance::Suggested);
+        dialog.set_close_response("cancel");
+
+        if !diff_text.is_empty() {
+            let label = gtk::Label::builder()
+                .label(diff_text)
+                .xalign(0.0)
+                .selectable(true)
+                .wrap(true)
+                .css_classes(["monospace"])
+                .build();
+            dialog.set_extra_child(Some(&label));
+        }
+
+        if dialog.choose_future(self).await != "import" {
+            return;
+        }
+
+        let has_prayer_times = bundle.prayer_times.is_some();
+        if let Some(prayer_times) = bundle.prayer_times {
+            let cache = imp.cache.borrow();
+            cache.set_prayer_times(prayer_times);
+
+            if let Err(e) = cache.save() {
+                eprintln!("Failed to save cache: {e}");
+            }
+        }
+
+        let district_id = new_preferences.district_id.borrow().clone();
+        let location_changed = self.apply_preferences(PreferencesJson {
+            preferences: new_preferences,
+        });
+        self.save_preferences();
+
+        if location_changed && !has_prayer_times {
+            self.request_prayer_times(district_id);
+        }
+
+        let toast = adw::Toast::new(&gettext("Settings imported."));
+        imp.toast_overlay.add_toast(toast);
+    }
+
+    fn refresh_location_lists(&self) {
+        let imp = self.imp();
+
+        let country = imp.country.borrow().clone();
+        let country_id = match imp.countries.borrow().get(&country) {
+            Some(v) => v.clone(),
+            None => match imp.countries_en.borrow().get(&country) {
+                Some(v) => v.clone(),
+                None => return,
+            },
+        };
+        let city = imp.city.borrow().clone();
+
+        let sender = imp.sender.borrow().clone().unwrap();
+
+        RUNTIME.spawn(async move {
+            let result = networking::get_location_lists(&country_id, &city).await;
+            sender.send(Message::LocationLists(result)).await.unwrap();
+        });
+    }
+
+    fn request_prayer_times(&self, district_id: String) {
+        let sender = self.imp().sender.borrow().clone().unwrap();
+
+        RUNTIME.spawn(async move {
+            let result = networking::get_prayer_times(&district_id).await;
+            sender.send(Message::PrayerTimes(result)).await.unwrap();
+        });
+    }
+
     pub fn init_second_tick(&self) {
         let imp = self.imp();
         let pref = imp.preferences.borrow();
 
-        // Check if prayer times on .json file still up to date
-        if !prayer::is_prayer_times_valid(&pref) {
-            println!("Prayer times are not valid, updating...");
+        // Check if cached prayer times are still up to date
+        if !prayer::is_prayer_times_valid(&imp.cache.borrow()) {
+            eprintln!("Prayer times are not valid, updating...");
 
             let district_id = pref.preferences.district_id.borrow().clone();
-            let sender = imp.sender.borrow().clone().unwrap();
-
-            RUNTIME.spawn(async move {
-                let result = networking::get_prayer_times(&district_id).await;
-                sender
-                    .send(Message::PrayerTimesArrived(result))
-                    .await
-                    .unwrap();
-            });
+            self.request_prayer_times(district_id);
         }
 
         self.on_second_tick();
 
         let self_clone = self.downgrade();
 
-        println!("Starting tick...");
+        eprintln!("Starting tick...");
 
         glib::timeout_add_seconds_local(1, move || {
             let self_clone = self_clone.upgrade().unwrap();
@@ -213,9 +634,46 @@ impl MainWindow {
         });
     }
 
+    /// Catches up right after waking from suspend, instead of waiting for the next tick.
+    fn watch_sleep(&self) {
+        let self_clone = self.downgrade();
+
+        // Without blocking the startup, e.g. until a missing system bus times out
+        glib::spawn_future_local(async move {
+            let connection = match gio::bus_get_future(gio::BusType::System).await {
+                Ok(c) => c,
+                Err(e) => {
+                    eprintln!("Couldn't connect to the system bus: {e}");
+                    return;
+                }
+            };
+
+            connection.signal_subscribe(
+                Some("org.freedesktop.login1"),
+                Some("org.freedesktop.login1.Manager"),
+                Some("PrepareForSleep"),
+                Some("/org/freedesktop/login1"),
+                None,
+                gio::DBusSignalFlags::NONE,
+                move |_, _, _, _, _, params| {
+                    let Some((going_to_sleep,)) = params.get::<(bool,)>() else {
+                        return;
+                    };
+
+                    if let Some(self_clone) = self_clone.upgrade() {
+                        if !going_to_sleep {
+                            eprintln!("Resumed from suspend");
+                            self_clone.update_prayer_time_labels();
+                            self_clone.on_second_tick();
+                        }
+                    }
+                },
+            );
+        });
+    }
+
     pub fn on_second_tick(&self) {
         let imp = self.imp();
-        let pref = &imp.preferences.borrow().preferences;
 
         // Update current time
         let now = Local::now();
@@ -225,44 +683,21 @@ impl MainWindow {
         let remaining_time = prayer::calculate_remaining_time(
             &imp.todays_prayers.borrow(),
             &imp.tomorrows_prayers.borrow(),
+            now.time(),
         );
 
         if let Some(r) = remaining_time.as_ref() {
-            let current_min = r.hours as u32 * 60 + r.minutes as u32;
-
             // Update labels:
             let time_format = format!("{:0>2}:{:0>2}:{:0>2}", r.hours, r.minutes, r.seconds);
             self.set_next_prayer_name(r.next_prayer.to_string());
             self.set_next_prayer_time(time_format);
             let current_prayer: u8 = r.next_prayer as u8;
             self.set_current_prayer(current_prayer as i32);
-
-            // Warning Time check:
-            let warn_min = *pref.warning_minutes.borrow() as u32;
-            let should_warn = current_min == warn_min && r.seconds == 0;
-
-            if should_warn {
-                // Send notification
-                let msg = formatx!(
-                    gettext("{min} minutes left {prayer}"),
-                    min = warn_min,
-                    prayer = r.next_prayer
-                )
-                .unwrap();
-
-                let notif = Notification::new(&msg);
-
-                self.application()
-                    .unwrap()
-                    .send_notification(Some("prayer-time-warn"), &notif);
-
-                std::thread::spawn(|| {
-                    play_alert();
-                });
-
-                self.present();
-            }
         }
+        self.update_tray(remaining_time.as_ref());
+
+        // Reminders & prayer times:
+        self.send_due_events();
     }
 
     pub fn listen_channel_message(&self, receiver: Receiver<Message>) {
@@ -276,24 +711,42 @@ impl MainWindow {
             loop {
                 match receiver.recv().await {
                     Ok(m) => match m {
-                        Message::CityListArrived(result, _country) => match result {
+                        Message::CityList(result, _country) => match result {
                             Ok(r) => {
-                                println!("City List Arrived: {r:?}");
+                                eprintln!("City List Arrived: {r:?}");
                                 self_clone.update_model_city(r, None);
                             }
                             Err(e) => eprintln!("Failed to fetch cities: {e}"),
                         },
-                        Message::DistrictListArrived(result, _city) => match result {
+                        Message::DistrictList(result, _city) => match result {
                             Ok(r) => {
-                                println!("District List Arrived: {r:?}");
+                                eprintln!("District List Arrived: {r:?}");
                                 self_clone.update_model_district(r, None);
                             }
                             Err(e) => eprintln!("Failed to fetch districts: {e}"),
                         },
-                        Message::PrayerTimesArrived(result) => match result {
+                        Message::LocationLists(result) => match result {
+                            Ok((cities, districts)) => {
+                                eprintln!("Location Lists Arrived");
+                                let city = imp.city.borrow().clone();
+                                let district = imp.district.borrow().clone();
+
+                                self_clone.update_model_city(cities.clone(), Some(city));
+                                self_clone.update_model_district(districts.clone(), Some(district));
+
+                                let cache = imp.cache.borrow();
+                                cache.set_locations(cities, districts);
+                                if let Err(e) = cache.save() {
+                                    eprintln!("Failed to save cache: {e}");
+                                }
+                            }
+                            Err(e) => eprintln!("Failed to refresh location lists: {e}"),
+                        },
+                        Message::PrayerTimes(result) => match result {
                             Ok(v) => {
                                 let pref = imp.preferences.borrow().clone();
-                                println!("Prayer Times Arrived");
+                                let cache = imp.cache.borrow().clone();
+                                eprintln!("Prayer Times Arrived");
                                 let mut hm = HashMap::new();
                                 for day in v {
                                     let key = day.MiladiTarihKisa.clone();
@@ -301,9 +754,16 @@ impl MainWindow {
                                     hm.insert(key, day);
                                 }
 
-                                // Save latest preferences struct to the .json file
-                                pref.cities.replace(imp.cities.borrow().clone());
-                                pref.districts.replace(imp.districts.borrow().clone());
+                                // Save latest lists & preferences to the .json files. The lists
+                                // only differ when they were just fetched for a new location.
+                                let cities = imp.cities.borrow().clone();
+                                let districts = imp.districts.borrow().clone();
+                                if !districts.is_empty()
+                                    && (*cache.cities.borrow() != cities
+                                        || *cache.districts.borrow() != districts)
+                                {
+                                    cache.set_locations(cities, districts);
+                                }
 
                                 pref.preferences
                                     .country
@@ -311,19 +771,25 @@ impl MainWindow {
                                 pref.preferences.city.replace(imp.city.borrow().clone());
 
                                 let district = imp.district.borrow().clone();
-                                let district_id =
-                                    imp.districts.borrow().get(&district).unwrap().clone();
+                                // Districts may still be loading after a location change on disk
+                                let district_id = match imp.districts.borrow().get(&district) {
+                                    Some(id) => id.clone(),
+                                    None => pref.preferences.district_id.borrow().clone(),
+                                };
 
                                 self_clone.set_district_title(district.clone());
                                 pref.preferences.district.replace(district);
                                 pref.preferences.district_id.replace(district_id);
+                                pref.preferences.remember_location();
 
-                                pref.prayer_times.replace(hm);
-                                pref.save().unwrap();
+                                cache.set_prayer_times(hm);
+                                if let Err(e) = cache.save() {
+                                    eprintln!("Failed to save cache: {e}");
+                                }
 
                                 // Update models
-                                let todays_prayers = prayer::get_prayers_of_day(&pref, 0);
-                                let tomorrows_prayers = prayer::get_prayers_of_day(&pref, 1);
+                                let todays_prayers = prayer::get_prayers_of_day(&cache, 0);
+                                let tomorrows_prayers = prayer::get_prayers_of_day(&cache, 1);
 
                                 // Set labels
                                 if let Some(today) = todays_prayers.clone() {
@@ -346,6 +812,8 @@ impl MainWindow {
                                 imp.todays_prayers.replace(todays_prayers);
                                 imp.tomorrows_prayers.replace(tomorrows_prayers);
                                 imp.preferences.replace(pref);
+                                imp.cache.replace(cache);
+                                self_clone.save_preferences();
                                 imp.visible_day.replace(0);
 
                                 self_clone.on_second_tick();
@@ -354,8 +822,9 @@ impl MainWindow {
                                 imp.toast_overlay.add_toast(toast);
 
                                 imp.navigation_view.pop_to_page(&imp.main_page.get());
+                                self_clone.emit_location_changed();
 
-                                println!("Prayer Times updated!");
+                                eprintln!("Prayer Times updated!");
                             }
                             Err(e) => {
                                 let toast =
@@ -418,7 +887,7 @@ impl MainWindow {
         RUNTIME.spawn(async move {
             let result = networking::get_city_list(&country_id).await;
             sender
-                .send(Message::CityListArrived(result, country_name_clone))
+                .send(Message::CityList(result, country_name_clone))
                 .await
                 .unwrap();
         });
@@ -458,7 +927,7 @@ impl MainWindow {
         RUNTIME.spawn(async move {
             let result = networking::get_district_list(&city_id).await;
             sender
-                .send(Message::DistrictListArrived(result, city_name_clone))
+                .send(Message::DistrictList(result, city_name_clone))
                 .await
                 .unwrap();
         });
@@ -484,24 +953,160 @@ impl MainWindow {
     }
 
     #[template_callback]
-    fn on_warn_min_changed(&self, param: ParamSpec, spin: SpinRow) {
-        let value: f64 = spin.property(param.name());
+    fn on_play_sound_changed(&self, _param: ParamSpec, row: adw::SwitchRow) {
+        let imp = self.imp();
+        let pref = imp.preferences.borrow();
+
+        pref.preferences.play_sound.replace(row.is_active());
+        self.schedule_save_preferences();
+    }
+
+    #[template_callback]
+    fn on_raise_mode_changed(&self, _param: ParamSpec, row: adw::ComboRow) {
+        let imp = self.imp();
+        let pref = imp.preferences.borrow();
+
+        pref.preferences
+            .raise_mode
+            .replace(RaiseMode::from(row.selected()));
+        self.schedule_save_preferences();
+    }
+
+    #[template_callback]
+    fn on_fullscreen_overlay_changed(&self, _param: ParamSpec, row: adw::SwitchRow) {
+        let imp = self.imp();
+        let pref = imp.preferences.borrow();
+
+        pref.preferences.fullscreen_overlay.replace(row.is_active());
+        self.schedule_save_preferences();
+    }
+
+    #[template_callback]
+    fn on_run_at_login_changed(&self, _param: ParamSpec, row: adw::SwitchRow) {
+        let enabled = row.is_active();
+        if enabled
+            == *self
+                .imp()
+                .preferences
+                .borrow()
+                .preferences
+                .run_at_login
+                .borrow()
+        {
+            return;
+        }
+
+        let self_clone = self.downgrade();
+        glib::spawn_future_local(async move {
+            let result = autostart::set_run_at_login(enabled).await;
+
+            let Some(self_clone) = self_clone.upgrade() else {
+                return;
+            };
+            let imp = self_clone.imp();
+
+            match result {
+                Ok(()) => {
+                    let pref = imp.preferences.borrow();
+                    pref.preferences.run_at_login.replace(enabled);
+                    self_clone.schedule_save_preferences();
+                }
+                Err(e) => {
+                    eprintln!("Couldn't change running at login: {e}");
+
+                    let toast = adw::Toast::new(&e);
+                    imp.toast_overlay.add_toast(toast);
+                    imp.run_at_login_row.set_active(!enabled);
+                }
+            }
+        });
+    }
+
+    #[template_callback]
+    fn on_pause_media_changed(&self, _param: ParamSpec, row: adw::SwitchRow) {
+        let imp = self.imp();
+        let pref = imp.preferences.borrow();
+
+        pref.preferences.pause_media.replace(row.is_active());
+        self.schedule_save_preferences();
+    }
 
+    #[template_callback]
+    fn on_quiet_hours_changed(&self) {
+        let imp = self.imp();
+        let pref = imp.preferences.borrow();
+
+        pref.preferences
+            .quiet_hours
+            .replace(imp.quiet_hours_row.enables_expansion());
+        pref.preferences
+            .quiet_hours_start
+            .replace(imp.quiet_hours_start_row.value() as u32);
+        pref.preferences
+            .quiet_hours_end
+            .replace(imp.quiet_hours_end_row.value() as u32);
+        self.schedule_save_preferences();
+    }
+
+    #[template_callback]
+    fn on_prayer_notification_changed(&self, param: ParamSpec, row: PrayerNotificationRow) {
         let imp = self.imp();
         let pref = imp.preferences.borrow();
 
-        pref.preferences.warning_minutes.replace(value as u8);
-        pref.save().unwrap();
+        let prayer = Prayer::from(row.prayer() as u8);
+
+        match param.name() {
+            "notify-before" => pref
+                .preferences
+                .set_notify_before(prayer, row.notify_before()),
+            "notify-at-time" => pref
+                .preferences
+                .set_notify_at_time(prayer, row.notify_at_time()),
+            "sound" => pref.preferences.set_prayer_sound(prayer, row.sound()),
+            "volume" => pref.preferences.set_volume(prayer, row.volume()),
+            "fade-in" => pref.preferences.set_fade_in(prayer, row.fade_in()),
+            "silent" => pref.preferences.set_silent(prayer, row.silent()),
+            "override-dnd" => pref
+                .preferences
+                .set_override_dnd(prayer, row.override_dnd()),
+            _ => return,
+        }
+
+        self.schedule_save_preferences();
     }
 
     #[template_callback]
     fn on_update_prayer_times_activated(&self, _button: ButtonRow) {
+        if let Err(e) = self.update_prayer_times() {
+            eprintln!("{e}");
+        }
+    }
+
+    /// Gets the prayer times of the selected district again.
+    pub fn update_prayer_times(&self) -> Result<(), String> {
         let imp = self.imp();
 
+        // The district list may not be loaded yet, e.g. offline at startup
         let district = imp.district.borrow().clone();
-        let district_id = imp.districts.borrow().get(&district).unwrap().clone();
+        let district_id = imp
+            .districts
+            .borrow()
+            .get(&district)
+            .cloned()
+            .unwrap_or_else(|| {
+                imp.preferences
+                    .borrow()
+                    .preferences
+                    .district_id
+                    .borrow()
+                    .clone()
+            });
 
-        let sender = imp.sender.borrow().clone().unwrap();
+        if district_id.is_empty() {
+            let message = gettext("Choose a district first");
+            imp.toast_overlay.add_toast(adw::Toast::new(&message));
+            return Err(message);
+        }
 
         let toast = adw::Toast::builder()
             .title(gettext("Getting Prayer Times..."))
@@ -509,13 +1114,9 @@ impl MainWindow {
             .build();
         imp.toast_overlay.add_toast(toast);
 
-        RUNTIME.spawn(async move {
-            let result = networking::get_prayer_times(&district_id).await;
-            sender
-                .send(Message::PrayerTimesArrived(result))
-                .await
-                .unwrap();
-        });
+        self.request_prayer_times(district_id);
+
+        Ok(())
     }
 
     // TODO: Add this feature later
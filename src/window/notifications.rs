@@ -0,0 +1,316 @@
+use std::time::Duration;
+
+use adw::prelude::*;
+use adw::subclass::prelude::ObjectSubclassIsExt;
+use chrono::{DateTime, Local, TimeDelta};
+use formatx::formatx;
+use gettextrs::gettext;
+use gtk::gio::{self, Notification};
+use gtk::glib;
+
+use super::{main_window, MainWindow};
+use crate::dnd::{self, Delivery, DesktopDnd};
+use crate::idle::{self, DesktopIdle};
+use crate::prayer::Prayer;
+use crate::prayeroverlay::PrayerOverlay;
+use crate::scheduler::{Event, EventKind};
+use crate::sound::{self, Sound};
+use crate::APP_ID;
+
+const SNOOZE_DELAY: Duration = Duration::from_secs(5 * 60);
+/// Prayed marks older than this can't silence any reminder anymore.
+const PRAYED_MAX_AGE: TimeDelta = TimeDelta::days(2);
+
+/// Action target of an event: prayer index, prayer time & minutes before it.
+type EventTarget = (i32, i64, i32);
+
+fn event_to_variant(event: &Event) -> glib::Variant {
+    let target: EventTarget = (
+        event.prayer.index() as i32,
+        event.prayer_time.and_utc().timestamp(),
+        event.kind.minutes(),
+    );
+
+    target.to_variant()
+}
+
+fn event_from_variant(variant: Option<&glib::Variant>) -> Option<Event> {
+    let (prayer, prayer_time, minutes) = variant?.get::<EventTarget>()?;
+    let prayer_time = DateTime::from_timestamp(prayer_time, 0)?.naive_utc();
+
+    Some(Event::new(
+        Prayer::from(prayer as u8),
+        EventKind::from_minutes(minutes),
+        prayer_time,
+    ))
+}
+
+/// Adds the actions of the notification buttons to the application, so they keep
+/// working while the window is closed to the tray.
+pub fn install_notification_actions(app: &adw::Application) {
+    let event_type = EventTarget::static_variant_type();
+
+    let snooze = gio::SimpleAction::new("snooze", Some(&event_type));
+    snooze.connect_activate(glib::clone!(
+        #[weak]
+        app,
+        move |_, target| {
+            if let (Some(window), Some(event)) = (main_window(&app), event_from_variant(target)) {
+                window.snooze(event);
+            }
+        }
+    ));
+
+    let stop_adhan = gio::SimpleAction::new("stop-adhan", None);
+    stop_adhan.connect_activate(|_, _| sound::stop());
+
+    let mark_prayed = gio::SimpleAction::new("mark-prayed", Some(&event_type));
+    mark_prayed.connect_activate(glib::clone!(
+        #[weak]
+        app,
+        move |_, target| {
+            if let (Some(window), Some(event)) = (main_window(&app), event_from_variant(target)) {
+                window.mark_prayed(event);
+            }
+        }
+    ));
+
+    app.add_action(&snooze);
+    app.add_action(&stop_adhan);
+    app.add_action(&mark_prayed);
+}
+
+impl MainWindow {
+    /// Sends the reminders & prayer time notifications that arrived since the last tick.
+    pub(super) fn send_due_events(&self) {
+        let imp = self.imp();
+
+        let events = imp
+            .scheduler
+            .borrow_mut()
+            .due_events(&imp.cache.borrow(), &imp.preferences.borrow().preferences);
+
+        for event in events {
+            self.emit_event(&event);
+            self.notify_event(event);
+        }
+    }
+
+    fn is_prayed(&self, event: &Event) -> bool {
+        self.imp().prayed.borrow().contains(&event.prayer_time)
+    }
+
+    pub fn mark_prayed(&self, event: Event) {
+        let cutoff = Local::now().naive_local() - PRAYED_MAX_AGE;
+
+        let mut prayed = self.imp().prayed.borrow_mut();
+        prayed.retain(|t| *t > cutoff);
+        prayed.insert(event.prayer_time);
+    }
+
+    pub fn snooze(&self, event: Event) {
+        let self_clone = self.downgrade();
+
+        glib::timeout_add_local_once(SNOOZE_DELAY, move || {
+            if let Some(self_clone) = self_clone.upgrade() {
+                self_clone.notify_event(event);
+            }
+        });
+    }
+
+    /// Asking the desktop about Do-Not-Disturb & idleness doesn't block the window.
+    fn notify_event(&self, event: Event) {
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = window)]
+            self,
+            async move { window.send_event_notification(event).await }
+        ));
+    }
+
+    async fn send_event_notification(&self, event: Event) {
+        // Reminders after a prayer time are only for the ones not prayed yet
+        if event.kind.minutes() < 0 && self.is_prayed(&event) {
+            return;
+        }
+
+        let (id, title, default_sound) = match event.kind {
+            // Sunrise isn't a prayer, it ends the time of Fajr
+            EventKind::PrayerTime if event.prayer == Prayer::Sunrise => {
+                ("prayer-time", gettext("Fajr time has ended"), Sound::Alert)
+            }
+            EventKind::PrayerTime => (
+                "prayer-time",
+                formatx!(
+                    gettext("It's time for {prayer}"),
+                    prayer = event.prayer.name()
+                )
+                .unwrap(),
+                Sound::PrayerTime,
+            ),
+            EventKind::Reminder(minutes) if minutes < 0 => (
+                "prayer-time-warn",
+                formatx!(
+                    gettext("{min} minutes passed since {prayer}"),
+                    min = -minutes,
+                    prayer = event.prayer.name()
+                )
+                .unwrap(),
+                Sound::Alert,
+            ),
+            EventKind::Reminder(minutes) => (
+                "prayer-time-warn",
+                formatx!(
+                    gettext("{min} minutes left {prayer}"),
+                    min = minutes,
+                    prayer = event.prayer
+                )
+                .unwrap(),
+                Sound::Alert,
+            ),
+        };
+
+        // Custom sounds are for the prayer times, reminders keep the alert
+        let custom_sound = match event.kind {
+            EventKind::PrayerTime => self
+                .imp()
+                .preferences
+                .borrow()
+                .preferences
+                .prayer_sound(event.prayer),
+            EventKind::Reminder(_) => None,
+        };
+
+        let notif = Notification::new(&title);
+        notif.set_body(Some(&self.event_body(&event)));
+        notif.set_icon(&gio::ThemedIcon::new(APP_ID));
+
+        let target = event_to_variant(&event);
+        notif.add_button_with_target_value(&gettext("Snooze 5 min"), "app.snooze", Some(&target));
+
+        let delivery = self.delivery(&event).await;
+        if delivery.urgent {
+            notif.set_priority(gio::NotificationPriority::Urgent);
+        }
+
+        let plays_adhan = delivery.audible
+            && *self
+                .imp()
+                .preferences
+                .borrow()
+                .preferences
+                .play_sound
+                .borrow()
+            && event.kind == EventKind::PrayerTime
+            && event.prayer != Prayer::Sunrise;
+        if plays_adhan {
+            notif.add_button(&gettext("Stop Adhan"), "app.stop-adhan");
+        }
+
+        if event.prayer != Prayer::Sunrise {
+            notif.add_button_with_target_value(
+                &gettext("Mark as Prayed"),
+                "app.mark-prayed",
+                Some(&target),
+            );
+        }
+
+        let sound = delivery
+            .audible
+            .then(|| Sound::custom_or(custom_sound, default_sound));
+        self.send_alert(id, &notif, sound, &event, &title).await;
+    }
+
+    /// e.g. "Maghrib is at 18:42 in KADIKÖY, İSTANBUL"
+    fn event_body(&self, event: &Event) -> String {
+        let location = self.imp().preferences.borrow().preferences.location();
+
+        formatx!(
+            gettext("{prayer} is at {time} in {location}"),
+            prayer = event.prayer.name(),
+            time = event.prayer_time.format("%H:%M"),
+            location = location.label()
+        )
+        .unwrap()
+    }
+
+    /// Silent prayers, quiet hours, muting & Do-Not-Disturb only show the notification.
+    async fn delivery(&self, event: &Event) -> Delivery {
+        let muted = self.muted_until().is_some();
+        let (silent, override_dnd) = {
+            let pref = &self.imp().preferences.borrow().preferences;
+
+            (
+                muted || pref.silent(event.prayer) || pref.is_quiet_hour(event.at.time()),
+                pref.override_dnd(event.prayer),
+            )
+        };
+
+        dnd::delivery(&DesktopDnd, silent, override_dnd).await
+    }
+
+    /// Shows `notif`, and plays `sound` & raises the window if there is one.
+    async fn send_alert(
+        &self,
+        id: &str,
+        notif: &Notification,
+        sound: Option<Sound>,
+        event: &Event,
+        title: &str,
+    ) {
+        self.application()
+            .unwrap()
+            .send_notification(Some(id), notif);
+
+        let Some(sound) = sound else {
+            return;
+        };
+
+        // Not borrowed while asking the desktop
+        let imp = self.imp();
+        let (raise_mode, fullscreen_overlay) = {
+            let pref = &imp.preferences.borrow().preferences;
+
+            if *pref.play_sound.borrow() {
+                sound::play(
+                    sound,
+                    pref.volume(event.prayer),
+                    pref.fade_in(event.prayer),
+                    *pref.pause_media.borrow(),
+                );
+            }
+
+            let raise_mode = *pref.raise_mode.borrow();
+            let fullscreen_overlay = *pref.fullscreen_overlay.borrow();
+            (raise_mode, fullscreen_overlay)
+        };
+
+        if !idle::should_raise(raise_mode, &DesktopIdle).await {
+            return;
+        }
+
+        if fullscreen_overlay {
+            self.show_overlay(event, title);
+        } else {
+            self.present();
+        }
+    }
+
+    /// Replaces the overlay of an earlier alert, if it wasn't dismissed.
+    fn show_overlay(&self, event: &Event, title: &str) {
+        let imp = self.imp();
+
+        if let Some(overlay) = imp.overlay.upgrade() {
+            overlay.close();
+        }
+
+        let overlay = PrayerOverlay::new(
+            &self.application().unwrap(),
+            title,
+            &self.event_body(event),
+            event.prayer_time,
+        );
+        overlay.present();
+
+        imp.overlay.set(Some(&overlay));
+    }
+}
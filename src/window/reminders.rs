@@ -0,0 +1,149 @@
+use adw::prelude::{ActionRowExt, PreferencesGroupExt, PreferencesPageExt, PreferencesRowExt};
+use adw::subclass::prelude::ObjectSubclassIsExt;
+use formatx::formatx;
+use gettextrs::gettext;
+use gtk::glib;
+use gtk::prelude::{ButtonExt, WidgetExt};
+
+use super::MainWindow;
+use crate::prayer::Prayer;
+use crate::preferences::MAX_REMINDER_MINUTES;
+
+fn reminder_title(minutes: i32) -> String {
+    if minutes < 0 {
+        formatx!(gettext("{min} minutes after"), min = -minutes).unwrap()
+    } else {
+        formatx!(gettext("{min} minutes before"), min = minutes).unwrap()
+    }
+}
+
+impl MainWindow {
+    /// Creates a group per prayer with a row to add new reminders.
+    pub(super) fn build_reminders_page(&self) {
+        let imp = self.imp();
+
+        let mut groups = Vec::new();
+
+        for prayer in Prayer::ALL {
+            let group = adw::PreferencesGroup::builder()
+                .title(prayer.name())
+                .build();
+
+            let limit = f64::from(MAX_REMINDER_MINUTES);
+            let spin_row = adw::SpinRow::with_range(-limit, limit, 1.0);
+            spin_row.set_title(&gettext("New Reminder"));
+            spin_row.set_subtitle(&gettext("Negative minutes remind after the prayer time"));
+            spin_row.set_value(15.0);
+
+            let add_button = gtk::Button::builder()
+                .icon_name("list-add-symbolic")
+                .tooltip_text(gettext("Add Reminder"))
+                .valign(gtk::Align::Center)
+                .css_classes(["flat"])
+                .build();
+            spin_row.add_suffix(&add_button);
+
+            // "At Prayer Time" already covers 0 minutes
+            spin_row.connect_value_notify(glib::clone!(
+                #[weak]
+                add_button,
+                move |row| add_button.set_sensitive(row.value() as i32 != 0)
+            ));
+
+            add_button.connect_clicked(glib::clone!(
+                #[weak(rename_to = window)]
+                self,
+                #[weak]
+                spin_row,
+                move |_| {
+                    let minutes = spin_row.value() as i32;
+                    if minutes == 0 {
+                        return;
+                    }
+
+                    let mut reminders = window
+                        .imp()
+                        .preferences
+                        .borrow()
+                        .preferences
+                        .reminders(prayer);
+
+                    if !reminders.contains(&minutes) {
+                        reminders.push(minutes);
+                        window.set_reminders(prayer, reminders);
+                    }
+                }
+            ));
+
+            group.add(&spin_row);
+            imp.reminders_page.add(&group);
+            groups.push(group);
+        }
+
+        imp.reminder_groups.replace(groups);
+        imp.reminder_rows
+            .replace(vec![Vec::new(); Prayer::ALL.len()]);
+    }
+
+    fn set_reminders(&self, prayer: Prayer, minutes: Vec<i32>) {
+        self.imp()
+            .preferences
+            .borrow()
+            .preferences
+            .set_reminders(prayer, minutes);
+
+        self.update_reminders_page();
+        self.schedule_save_preferences();
+    }
+
+    /// Recreates the reminder rows from the preferences.
+    pub(super) fn update_reminders_page(&self) {
+        let imp = self.imp();
+        let pref = &imp.preferences.borrow().preferences;
+        let groups = imp.reminder_groups.borrow();
+        let mut rows = imp.reminder_rows.borrow_mut();
+
+        for ((prayer, group), old_rows) in Prayer::ALL
+            .into_iter()
+            .zip(groups.iter())
+            .zip(rows.iter_mut())
+        {
+            for row in old_rows.drain(..) {
+                group.remove(&row);
+            }
+
+            for minutes in pref.reminders(prayer) {
+                let row = adw::ActionRow::builder()
+                    .title(reminder_title(minutes))
+                    .build();
+
+                let remove_button = gtk::Button::builder()
+                    .icon_name("user-trash-symbolic")
+                    .tooltip_text(gettext("Remove Reminder"))
+                    .valign(gtk::Align::Center)
+                    .css_classes(["flat"])
+                    .build();
+                row.add_suffix(&remove_button);
+
+                remove_button.connect_clicked(glib::clone!(
+                    #[weak(rename_to = window)]
+                    self,
+                    move |_| {
+                        let mut reminders = window
+                            .imp()
+                            .preferences
+                            .borrow()
+                            .preferences
+                            .reminders(prayer);
+                        reminders.retain(|m| *m != minutes);
+
+                        window.set_reminders(prayer, reminders);
+                    }
+                ));
+
+                group.add(&row);
+                old_rows.push(row);
+            }
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use adw::subclass::prelude::ObjectSubclassIsExt;
+use chrono::{NaiveDate, NaiveDateTime};
+use gtk::gio::{self, prelude::ApplicationExt};
+use gtk::prelude::GtkWindowExt;
+
+use super::MainWindow;
+use crate::cli;
+use crate::dbusservice::{self, PrayerSource};
+use crate::prayer::{self, Prayer, PrayerTimesWithDate};
+use crate::preferences::Location;
+use crate::scheduler::Event;
+
+impl PrayerSource for MainWindow {
+    fn times_of(&self, date: NaiveDate) -> Option<PrayerTimesWithDate> {
+        prayer::get_prayers_of_date(&self.imp().cache.borrow(), date)
+    }
+
+    fn next_prayer(&self, now: NaiveDateTime) -> Option<(Prayer, NaiveDateTime)> {
+        cli::next_prayer(&self.imp().cache.borrow(), now)
+    }
+
+    fn location(&self) -> Location {
+        self.imp().preferences.borrow().preferences.location()
+    }
+
+    fn refresh(&self) -> Result<(), String> {
+        self.update_prayer_times()
+    }
+}
+
+impl MainWindow {
+    fn dbus_connection(&self) -> Option<gio::DBusConnection> {
+        self.application()?.dbus_connection()
+    }
+
+    /// Tells the D-Bus service's listeners about a due reminder or prayer time.
+    pub(super) fn emit_event(&self, event: &Event) {
+        if let Some(connection) = self.dbus_connection() {
+            dbusservice::emit_event(&connection, event);
+        }
+    }
+
+    pub(super) fn emit_location_changed(&self) {
+        if let Some(connection) = self.dbus_connection() {
+            dbusservice::emit_location_changed(&connection, &self.location());
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use std::time::{Duration, Instant};
+
+use adw::subclass::prelude::ObjectSubclassIsExt;
+use chrono::{Local, TimeDelta};
+use gtk::prelude::WidgetExt;
+
+use super::MainWindow;
+use crate::prayer::{self, Prayer, RemainingTime};
+use crate::trayicon::{self, TrayStatus};
+use crate::traypixmap::IconState;
+
+/// The tray is updated every second only while the window is shown.
+const TRAY_HIDDEN_INTERVAL: Duration = Duration::from_secs(60);
+
+impl MainWindow {
+    /// Seconds are left out while the window is hidden, as they would be outdated.
+    pub(super) fn update_tray(&self, remaining_time: Option<&RemainingTime>) {
+        let imp = self.imp();
+        let visible = self.is_visible();
+
+        let due = visible
+            || imp
+                .tray_updated
+                .get()
+                .is_none_or(|t| t.elapsed() >= TRAY_HIDDEN_INTERVAL);
+        if !due {
+            return;
+        }
+        imp.tray_updated.set(Some(Instant::now()));
+
+        let next_prayer = remaining_time.map(|r| {
+            let time = if visible {
+                format!("{:0>2}:{:0>2}:{:0>2}", r.hours, r.minutes, r.seconds)
+            } else {
+                format!("{:0>2}:{:0>2}", r.hours, r.minutes)
+            };
+
+            format!("{} {time}", r.next_prayer)
+        });
+
+        let times = imp
+            .todays_prayers
+            .borrow()
+            .as_ref()
+            .map(|t| {
+                Prayer::ALL
+                    .iter()
+                    .map(|p| (p.name(), t.time_of(*p).to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let icon = remaining_time.and_then(|r| self.tray_icon_state(r));
+
+        let muted_until = self.muted_until().map(|t| t.format("%H:%M").to_string());
+
+        let locations = imp
+            .preferences
+            .borrow()
+            .preferences
+            .recent_locations
+            .borrow()
+            .iter()
+            .map(|l| (l.label(), l.district_id.clone()))
+            .collect();
+
+        let status = TrayStatus {
+            next_prayer,
+            times,
+            icon,
+            muted_until,
+            locations,
+        };
+        if *imp.tray_status.borrow() != status {
+            imp.tray_status.replace(status.clone());
+            trayicon::update_status(status);
+        }
+    }
+
+    /// Updates the tray right away, e.g. after an action from its menu.
+    pub fn refresh_tray(&self) {
+        let imp = self.imp();
+
+        let remaining_time = prayer::calculate_remaining_time(
+            &imp.todays_prayers.borrow(),
+            &imp.tomorrows_prayers.borrow(),
+            Local::now().time(),
+        );
+
+        imp.tray_updated.set(None);
+        self.update_tray(remaining_time.as_ref());
+    }
+
+    fn tray_icon_state(&self, remaining_time: &RemainingTime) -> Option<IconState> {
+        let imp = self.imp();
+        let pref = &imp.preferences.borrow().preferences;
+
+        let now = Local::now().naive_local();
+        let (previous, next) = prayer::surrounding_prayer_times(&imp.cache.borrow(), now)?;
+        let progress =
+            (now - previous).num_seconds() as f64 / (next - previous).num_seconds() as f64;
+
+        let r = remaining_time;
+        let remaining_seconds =
+            u32::from(r.hours) * 3600 + u32::from(r.minutes) * 60 + u32::from(r.seconds);
+        let remaining_minutes = remaining_seconds.div_ceil(60);
+
+        Some(IconState {
+            remaining_minutes,
+            progress,
+            warning: pref.is_warning(r.next_prayer, remaining_minutes),
+        })
+    }
+
+    /// Alerts only show their notification until then.
+    pub fn muted_until(&self) -> Option<chrono::NaiveDateTime> {
+        let imp = self.imp();
+        let now = Local::now().naive_local();
+
+        match imp.muted_until.get() {
+            Some(until) if until > now => Some(until),
+            Some(_) => {
+                imp.muted_until.set(None);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn mute_for(&self, hours: u32) {
+        let until = Local::now().naive_local() + TimeDelta::hours(hours.into());
+        eprintln!("Muted until {until}");
+
+        self.imp().muted_until.set(Some(until));
+        self.refresh_tray();
+    }
+
+    pub fn unmute(&self) {
+        self.imp().muted_until.set(None);
+        self.refresh_tray();
+    }
+
+    /// Switches to one of the recent locations and gets its prayer times.
+    pub fn switch_location(&self, district_id: &str) {
+        let imp = self.imp();
+
+        let new_pref = imp.preferences.borrow().clone();
+        let location = new_pref
+            .preferences
+            .recent_locations
+            .borrow()
+            .iter()
+            .find(|l| l.district_id == district_id)
+            .cloned();
+        let Some(location) = location else {
+            return;
+        };
+
+        eprintln!("Switching location to {}", location.label());
+        new_pref.preferences.set_location(location);
+
+        if self.apply_preferences(new_pref) {
+            self.save_preferences();
+            self.request_prayer_times(district_id.to_string());
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! Generates the files in `export/` that are made from the code, run with
+//! `cargo xtask export` after changing them and commit the results.
+
+use std::{env, fs, path::Path, process::ExitCode};
+
+use clap::CommandFactory;
+use clap_complete::{generate_to, Shell};
+
+#[allow(dead_code)]
+mod args {
+    include!("../../src/args.rs");
+}
+
+const BIN_NAME: &str = "vaktisalah-gtk-rs";
+const DBUS_SERVICE: &str = "io.github.eminfedar.vaktisalah-gtk-rs.service";
+
+/// Stops when clap_complete's output no longer has what the location completions
+/// hook into, rather than writing a broken script.
+fn expect_in<'a>(shell: Shell, completions: &str, marker: &'a str) -> &'a str {
+    if !completions.contains(marker) {
+        panic!(
+            "The {shell} completions generated by clap_complete have no {marker:?} anymore. \
+             Update with_location_completions() in xtask/src/main.rs for its new output."
+        );
+    }
+
+    marker
+}
+
+/// Completes `config set location` with the names the app cached, the rest is generated.
+fn with_location_completions(shell: Shell, completions: String) -> String {
+    match shell {
+        Shell::Bash => {
+            // clap_complete names the cases of the subcommands differently from the
+            // commands it matches them with when the binary's name has dashes
+            let completions =
+                completions.replace("vaktisalah__subcmd__gtk__subcmd__rs", "vaktisalah__gtk__rs");
+            expect_in(shell, &completions, "_vaktisalah__gtk__rs() {");
+            let registration = expect_in(shell, &completions, "-F _vaktisalah__gtk__rs ");
+
+            let (generated, rest) = completions
+                .split_once(expect_in(shell, &completions, "\nif [["))
+                .unwrap();
+            format!(
+                r#"{generated}
+_vaktisalah-gtk-rs_locations() {{
+    if [[ "${{COMP_WORDS[COMP_CWORD-2]}}" == "set" && "${{COMP_WORDS[COMP_CWORD-1]}}" == "location" ]]; then
+        local IFS=$'\n'
+        COMPREPLY=($(compgen -W "$({BIN_NAME} config locations 2>/dev/null)" -- "${{COMP_WORDS[COMP_CWORD]}}"))
+    else
+        _vaktisalah__gtk__rs "$@"
+    fi
+}}
+
+if [[{}"#,
+                rest.replace(registration, "-F _vaktisalah-gtk-rs_locations ")
+            )
+        }
+        Shell::Zsh => {
+            expect_in(shell, &completions, "_vaktisalah-gtk-rs() {");
+
+            let (generated, _) = completions
+                .split_once(expect_in(shell, &completions, "\nif [ \"$funcstack[1]\""))
+                .unwrap();
+            format!(
+                r#"{generated}
+_vaktisalah-gtk-rs_locations() {{
+    if [[ "${{words[CURRENT-2]}}" == "set" && "${{words[CURRENT-1]}}" == "location" ]]; then
+        local -a locations
+        locations=("${{(@f)$({BIN_NAME} config locations 2>/dev/null)}}")
+        compadd -a locations
+    else
+        _vaktisalah-gtk-rs "$@"
+    fi
+}}
+
+compdef _vaktisalah-gtk-rs_locations {BIN_NAME}
+if [ "$funcstack[1]" = "_vaktisalah-gtk-rs" ]; then
+    _vaktisalah-gtk-rs_locations "$@"
+fi
+"#
+            )
+        }
+        _ => format!(
+            "{completions}complete -c {BIN_NAME} -n \"__fish_seen_subcommand_from config; and \
+             __fish_seen_subcommand_from set; and __fish_seen_subcommand_from location\" \
+             -f -a \"({BIN_NAME} config locations 2>/dev/null)\"\n"
+        ),
+    }
+}
+
+fn generate_cli_docs() {
+    let mut cmd = args::Cli::command();
+
+    let completions_dir = "export/completions";
+    fs::create_dir_all(completions_dir).unwrap();
+
+    for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+        let path = generate_to(shell, &mut cmd, BIN_NAME, completions_dir).unwrap();
+        let completions = fs::read_to_string(&path).unwrap();
+        fs::write(&path, with_location_completions(shell, completions)).unwrap();
+    }
+
+    // A page for every subcommand too, the main page refers to them
+    let man_dir = "export/man";
+    fs::create_dir_all(man_dir).unwrap();
+    clap_mangen::generate_to(cmd, man_dir).unwrap();
+}
+
+/// The D-Bus activation file of the .deb, whose binary is in /usr/bin. The Flatpak
+/// manifest fills in /app/bin itself.
+fn generate_dbus_service() {
+    let template = fs::read_to_string(format!("data/{DBUS_SERVICE}.in")).unwrap();
+
+    fs::write(
+        format!("export/{DBUS_SERVICE}"),
+        template.replace("@bindir@", "/usr/bin"),
+    )
+    .unwrap();
+}
+
+fn main() -> ExitCode {
+    // Paths are relative to the repository
+    env::set_current_dir(Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap()).unwrap();
+
+    match env::args().nth(1).as_deref() {
+        Some("export") => {
+            generate_cli_docs();
+            generate_dbus_service();
+
+            ExitCode::SUCCESS
+        }
+        _ => {
+            eprintln!("Usage: cargo xtask export");
+            eprintln!();
+            eprintln!(
+                "  export  Writes the shell completions, man pages & D-Bus service to export/"
+            );
+
+            ExitCode::FAILURE
+        }
+    }
+}